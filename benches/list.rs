@@ -0,0 +1,51 @@
+//! Time `list` against a store of 1000 configs, the size a generated
+//! per-project or per-registry collection reaches.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{fs, path::PathBuf, process::Command};
+
+const CONFIGS: usize = 1000;
+
+/// A fake home directory with `CONFIGS` configs and some metadata.
+fn store() -> PathBuf {
+    let home = std::env::temp_dir().join(format!("cargo-config-bench-{}", std::process::id()));
+    let dir = home.join(".cargo/cargo-config");
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut meta = String::new();
+    for i in 0..CONFIGS {
+        fs::write(
+            dir.join(format!("config-{i:04}.toml")),
+            "[build]\njobs = 4\n",
+        )
+        .unwrap();
+        if i % 10 == 0 {
+            meta.push_str(&format!("[config-{i:04}]\ndescription = \"config {i}\"\n"));
+        }
+    }
+    fs::write(dir.join("cargo-config-meta"), meta).unwrap();
+    fs::write(dir.join("cargo-config-current"), "config-0000").unwrap();
+    home
+}
+
+fn list(c: &mut Criterion) {
+    let home = store();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-config-profiles"))
+            .args(args)
+            .env("HOME", &home)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    };
+
+    c.bench_function("list 1000 configs", |b| b.iter(|| run(&["list"])));
+    c.bench_function("list 1000 configs with columns", |b| {
+        b.iter(|| run(&["list", "--columns", "name,active,description,size"]))
+    });
+
+    fs::remove_dir_all(&home).unwrap();
+}
+
+criterion_group!(benches, list);
+criterion_main!(benches);
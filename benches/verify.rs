@@ -0,0 +1,63 @@
+//! Time `verify --all` and `lint --all` against a store of 1000 configs, one
+//! thread against the default of one per CPU.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{fmt::Write, fs, path::PathBuf, process::Command};
+
+const CONFIGS: usize = 1000;
+
+/// A fake home directory with `CONFIGS` configs of a realistic size, with a
+/// table per target and registry, so parsing dominates.
+fn store() -> PathBuf {
+    let home =
+        std::env::temp_dir().join(format!("cargo-config-bench-verify-{}", std::process::id()));
+    let dir = home.join(".cargo/cargo-config");
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut config =
+        String::from("[build]\njobs = 4\nrustflags = [\"-C\", \"target-cpu=native\"]\n");
+    for i in 0..50 {
+        write!(
+            config,
+            "\n[target.target-{i}]\nlinker = \"cc-{i}\"\nrustflags = [\"-C\", \"opt-level={}\"]\n\
+             \n[registries.registry-{i}]\nindex = \"sparse+https://registry-{i}.example.com/\"\n",
+            i % 4
+        )
+        .unwrap();
+    }
+    for i in 0..CONFIGS {
+        fs::write(dir.join(format!("config-{i:04}.toml")), &config).unwrap();
+    }
+    fs::write(dir.join("cargo-config-current"), "config-0000").unwrap();
+    home
+}
+
+fn verify(c: &mut Criterion) {
+    let home = store();
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_cargo-config-profiles"))
+            .args(args)
+            .env("HOME", &home)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    };
+
+    c.bench_function("verify 1000 configs, 1 job", |b| {
+        b.iter(|| run(&["verify", "--all", "--jobs", "1"]))
+    });
+    c.bench_function("verify 1000 configs, all CPUs", |b| {
+        b.iter(|| run(&["verify", "--all"]))
+    });
+    c.bench_function("lint 1000 configs, 1 job", |b| {
+        b.iter(|| run(&["lint", "--all", "--jobs", "1"]))
+    });
+    c.bench_function("lint 1000 configs, all CPUs", |b| {
+        b.iter(|| run(&["lint", "--all"]))
+    });
+
+    fs::remove_dir_all(&home).unwrap();
+}
+
+criterion_group!(benches, verify);
+criterion_main!(benches);
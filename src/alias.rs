@@ -0,0 +1,180 @@
+use owo_colors::OwoColorize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::{current_config, existing_config_path};
+
+/// Built-in cargo commands, which always win over an alias of the same name.
+const BUILTIN_COMMANDS: &[&str] = &[
+    "add",
+    "bench",
+    "build",
+    "check",
+    "clean",
+    "config",
+    "doc",
+    "fetch",
+    "fix",
+    "generate-lockfile",
+    "help",
+    "info",
+    "init",
+    "install",
+    "locate-project",
+    "login",
+    "logout",
+    "metadata",
+    "new",
+    "owner",
+    "package",
+    "pkgid",
+    "publish",
+    "read-manifest",
+    "remove",
+    "report",
+    "run",
+    "rustc",
+    "rustdoc",
+    "search",
+    "test",
+    "tree",
+    "uninstall",
+    "update",
+    "vendor",
+    "verify-project",
+    "version",
+    "yank",
+];
+
+/// The config to edit: `name`, or the active config.
+fn target(name: Option<&str>) -> io::Result<(String, PathBuf)> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => current_config()?.ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No config is active, pass --config",
+        ))?,
+    };
+    let path = existing_config_path(&name)?;
+    Ok((name, path))
+}
+
+fn load(name: &str, path: &Path) -> io::Result<DocumentMut> {
+    fs::read_to_string(path)?
+        .parse::<DocumentMut>()
+        .map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} is not valid TOML: {err}"),
+            )
+        })
+}
+
+/// An alias's expansion as cargo runs it, whether written as a string or as
+/// an array of arguments.
+fn expansion(item: &Item) -> String {
+    match item.as_value() {
+        Some(Value::String(expansion)) => expansion.value().clone(),
+        Some(Value::Array(args)) => args
+            .iter()
+            .map(|arg| arg.as_str().map_or_else(|| arg.to_string(), str::to_string))
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => item.to_string().trim().to_string(),
+    }
+}
+
+/// Print the `[alias]` entries of `config`, or the active config.
+pub fn list_aliases(config: Option<&str>) -> io::Result<()> {
+    let (name, path) = target(config)?;
+    let doc = load(&name, &path)?;
+
+    println!("Cargo aliases in {name}:");
+    if let Some(aliases) = doc.get("alias").and_then(Item::as_table_like) {
+        for (alias, item) in aliases.iter() {
+            println!("- {alias} = {}", expansion(item));
+        }
+    }
+    Ok(())
+}
+
+/// Set `alias` to run `expansion` in `config`, or the active config, leaving
+/// the rest of the file as it is.
+pub fn set_alias(config: Option<&str>, alias: &str, expansion: &str) -> io::Result<()> {
+    check_alias(alias, expansion)?;
+    let (name, path) = target(config)?;
+    let mut doc = load(&name, &path)?;
+
+    let aliases = doc
+        .entry("alias")
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("alias in {name} is not a table"),
+        ))?;
+    aliases.insert(alias, toml_edit::value(expansion.trim()));
+
+    tracing::debug!(name, alias, expansion, "setting cargo alias");
+    // Written in place so the hard link to an active config stays intact.
+    fs::write(&path, doc.to_string())?;
+    println!(
+        "Success:   {}  Set cargo alias {alias} in {name}",
+        "✓".green()
+    );
+    Ok(())
+}
+
+/// Remove `alias` from `config`, or the active config, dropping `[alias]` if
+/// it ends up empty.
+pub fn remove_alias(config: Option<&str>, alias: &str) -> io::Result<()> {
+    let (name, path) = target(config)?;
+    let mut doc = load(&name, &path)?;
+
+    let aliases = doc.get_mut("alias").and_then(Item::as_table_like_mut);
+    let Some(aliases) = aliases.filter(|aliases| aliases.contains_key(alias)) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{name} has no cargo alias {alias}"),
+        ));
+    };
+    aliases.remove(alias);
+    if aliases.is_empty() {
+        doc.remove("alias");
+    }
+
+    tracing::debug!(name, alias, "removing cargo alias");
+    fs::write(&path, doc.to_string())?;
+    println!(
+        "Success:   {}  Removed cargo alias {alias} from {name}",
+        "✓".green()
+    );
+    Ok(())
+}
+
+/// Refuse aliases cargo would ignore or fail to run.
+fn check_alias(alias: &str, expansion: &str) -> io::Result<()> {
+    let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+
+    if alias.is_empty() || alias.chars().any(char::is_whitespace) || alias.starts_with('-') {
+        return invalid(format!("{alias:?} is not a valid alias name"));
+    }
+    if BUILTIN_COMMANDS.contains(&alias) {
+        return invalid(format!(
+            "{alias} is a built-in cargo command, cargo would ignore the alias"
+        ));
+    }
+    match expansion.split_whitespace().next() {
+        None => invalid(format!("The expansion of {alias} is empty")),
+        Some(command) if command.starts_with('-') => invalid(format!(
+            "The expansion of {alias} must start with a cargo command, not {command}"
+        )),
+        Some(_) if expansion.contains(['\n', '\r']) => {
+            invalid(format!("The expansion of {alias} must be a single line"))
+        }
+        Some(_) => Ok(()),
+    }
+}
@@ -0,0 +1,104 @@
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::io;
+
+use crate::global;
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Ok,
+    Failed,
+}
+
+/// How one item of a batch went, as printed by `--json`.
+#[derive(Serialize, JsonSchema)]
+pub struct Outcome<'a> {
+    name: &'a str,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Run `op` on each of `names` in turn, stopping at the first failure, which
+/// is returned. Successes are reported with the message from `done`, or with
+/// `json` every outcome is printed as a JSON array at the end instead. Under
+/// `--keep-going` every name is tried and a summary of the failures returned.
+pub fn run_batch(
+    names: &[String],
+    json: bool,
+    done: impl Fn(&str) -> String,
+    mut op: impl FnMut(&str) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut outcomes = vec![];
+    let mut failure = None;
+
+    for name in names {
+        match op(name) {
+            Ok(()) => {
+                if !json {
+                    println!("Success:   {}  {}", "✓".green(), done(name));
+                }
+                outcomes.push(Outcome {
+                    name,
+                    status: Status::Ok,
+                    error: None,
+                });
+            }
+            Err(err) if global().keep_going => {
+                if !json {
+                    report_failure(&err);
+                }
+                outcomes.push(Outcome {
+                    name,
+                    status: Status::Failed,
+                    error: Some(err.to_string()),
+                });
+            }
+            Err(err) => {
+                outcomes.push(Outcome {
+                    name,
+                    status: Status::Failed,
+                    error: Some(err.to_string()),
+                });
+                failure = Some(err);
+                break;
+            }
+        }
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&outcomes).map_err(io::Error::other)?
+        );
+    }
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome.status, Status::Failed))
+        .map(|outcome| outcome.name.to_string())
+        .collect::<Vec<_>>();
+    summary(&failed, names.len())
+}
+
+/// Print a failure `--keep-going` moved past.
+pub fn report_failure(err: &io::Error) {
+    eprintln!("Failed:    {}  {err}", "✗".red());
+}
+
+/// The result of a `--keep-going` run over `total` items: an error naming
+/// every item in `failed`, if any.
+pub fn summary(failed: &[String], total: usize) -> io::Result<()> {
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "{} of {total} failed: {}",
+        failed.len(),
+        failed.join(", ")
+    )))
+}
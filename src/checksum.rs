@@ -0,0 +1,82 @@
+use clap::ValueEnum;
+use sha2::{Digest, Sha256};
+use std::{fs, io};
+
+use crate::{config_names, existing_config_path, global};
+
+/// A checksum algorithm, as chosen with `--checksum-algo`.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Algo {
+    /// BLAKE3, several times faster than SHA-256 on large files.
+    #[default]
+    Blake3,
+    Sha256,
+}
+
+impl Algo {
+    fn name(self) -> &'static str {
+        match self {
+            Algo::Blake3 => "blake3",
+            Algo::Sha256 => "sha256",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Algo> {
+        [Algo::Blake3, Algo::Sha256]
+            .into_iter()
+            .find(|algo| algo.name() == name)
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Hex digest of `content` with `algo`.
+pub fn hash_with(algo: Algo, content: &[u8]) -> String {
+    match algo {
+        Algo::Blake3 => blake3::hash(content).to_hex().to_string(),
+        Algo::Sha256 => hex(&Sha256::digest(content)),
+    }
+}
+
+/// Hex digest of `content` with the `--checksum-algo` algorithm. Every
+/// checksum the tool takes goes through here.
+pub fn hash(content: &[u8]) -> String {
+    hash_with(global().checksum_algo, content)
+}
+
+/// `content`'s digest prefixed with the algorithm, such as `sha256:...`, for
+/// checksums that are stored and compared on a later run.
+pub fn tagged(content: &[u8]) -> String {
+    let algo = global().checksum_algo;
+    format!("{}:{}", algo.name(), hash_with(algo, content))
+}
+
+/// Whether `content` matches a [`tagged`] checksum, hashing with the
+/// algorithm it was recorded with. Untagged checksums are SHA-256, as
+/// recorded before the algorithm could be chosen.
+pub fn matches_tagged(recorded: &str, content: &[u8]) -> bool {
+    let (algo, digest) = match recorded.split_once(':') {
+        Some((name, digest)) => match Algo::from_name(name) {
+            Some(algo) => (algo, digest),
+            None => return false,
+        },
+        None => (Algo::Sha256, recorded),
+    };
+    hash_with(algo, content) == digest
+}
+
+/// Print the checksum of each of `names`, or of every config, as
+/// `<checksum>  <name>`, the layout of `b3sum` and `sha256sum`.
+pub fn integrity(names: &[String]) -> io::Result<()> {
+    let names = match names {
+        [] => config_names()?,
+        names => names.to_vec(),
+    };
+    for name in names {
+        let content = fs::read(existing_config_path(&name)?)?;
+        println!("{}  {name}", hash(&content));
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Commands that take text on stdin and put it on the clipboard, in the order
+/// they are tried on this platform.
+fn candidates() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        return vec![("pbcopy", &[])];
+    }
+    if cfg!(windows) {
+        return vec![("clip", &[])];
+    }
+
+    let mut candidates = vec![];
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        candidates.push(("wl-copy", &[][..]));
+    }
+    if env::var_os("DISPLAY").is_some() {
+        candidates.push(("xclip", &["-selection", "clipboard"][..]));
+        candidates.push(("xsel", &["--clipboard", "--input"][..]));
+    }
+    candidates
+}
+
+/// Put `text` on the system clipboard through the platform's clipboard
+/// command. Errors on a headless system, or when none of the commands are
+/// installed.
+pub fn copy(text: &str) -> io::Result<()> {
+    let candidates = candidates();
+    if candidates.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "No clipboard is available without a display, drop --clipboard to print instead",
+        ));
+    }
+
+    for (program, args) in &candidates {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                tracing::debug!(program, "clipboard command not installed");
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        tracing::debug!(program, "copying to clipboard");
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "{program} failed with {status}, could not copy to the clipboard"
+            )));
+        }
+        return Ok(());
+    }
+
+    let programs = candidates.iter().map(|(program, _)| *program);
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "No clipboard command found, install one of {}",
+            programs.collect::<Vec<_>>().join(", ")
+        ),
+    ))
+}
@@ -0,0 +1,129 @@
+use std::{env, process::Command};
+
+use toml::{Table, Value};
+
+use crate::{global, warning};
+
+/// A cargo release, as `(major, minor)`.
+pub type Version = (u64, u64);
+
+/// Config keys cargo only honours from a given release on, as dotted paths
+/// where `*` matches any one key, with the release and what the key does.
+const GATED_KEYS: &[(&str, Version, &str)] = &[
+    (
+        "registries.crates-io.protocol",
+        (1, 68),
+        "the sparse registry protocol",
+    ),
+    (
+        "registry.global-credential-providers",
+        (1, 74),
+        "credential providers",
+    ),
+    (
+        "registry.credential-provider",
+        (1, 74),
+        "credential providers",
+    ),
+    (
+        "registries.*.credential-provider",
+        (1, 74),
+        "credential providers",
+    ),
+    (
+        "resolver.incompatible-rust-versions",
+        (1, 84),
+        "the MSRV-aware resolver",
+    ),
+    (
+        "cache.auto-clean-frequency",
+        (1, 88),
+        "automatic cache cleaning",
+    ),
+    ("build.build-dir", (1, 91), "a separate build directory"),
+];
+
+/// A key in a config that the installed cargo is too old for.
+pub struct TooNew {
+    pub path: String,
+    pub since: Version,
+    pub feature: &'static str,
+}
+
+/// The version of the cargo that would read the config, from `cargo
+/// --version`, or `None` if it cannot be run or its output is not understood.
+pub fn cargo_version() -> Option<Version> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.arg("--version");
+    if let Some(dir) = &global().target_cargo_home {
+        command.env("CARGO_HOME", dir);
+    }
+    let output = command.output().ok()?;
+    let version = parse_version(&String::from_utf8_lossy(&output.stdout));
+    tracing::debug!(?version, "detected cargo version");
+    version
+}
+
+/// Parse `cargo 1.95.0 (f2d3ce0bd 2026-03-21)` into `(1, 95)`.
+fn parse_version(output: &str) -> Option<Version> {
+    let version = output.split_whitespace().nth(1)?;
+    let mut parts = version.split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// The keys in `table` that `cargo` is too old to honour.
+pub fn too_new(table: &Table, cargo: Version) -> Vec<TooNew> {
+    let mut found = vec![];
+    for &(pattern, since, feature) in GATED_KEYS {
+        if cargo >= since {
+            continue;
+        }
+        let pattern = pattern.split('.').collect::<Vec<_>>();
+        find(table, &pattern, String::new(), &mut |path| {
+            found.push(TooNew {
+                path,
+                since,
+                feature,
+            })
+        });
+    }
+    found
+}
+
+fn find(table: &Table, pattern: &[&str], prefix: String, found: &mut impl FnMut(String)) {
+    let Some((first, rest)) = pattern.split_first() else {
+        return;
+    };
+    for (key, value) in table {
+        if *first != "*" && first != key {
+            continue;
+        }
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match (rest.is_empty(), value) {
+            (true, _) => found(path),
+            (false, Value::Table(table)) => find(table, rest, path, found),
+            (false, _) => {}
+        }
+    }
+}
+
+/// Warn that a key does nothing with the installed `cargo`, naming the config
+/// when checking several.
+pub fn warn_too_new(name: Option<&str>, too_new: &TooNew, cargo: Version) {
+    let path = match name {
+        Some(name) => format!("{} in {name}", too_new.path),
+        None => too_new.path.clone(),
+    };
+    let (major, minor) = too_new.since;
+    warning::print(format!(
+        "{path} needs cargo {major}.{minor} or newer for {}, but cargo is {}.{}",
+        too_new.feature, cargo.0, cargo.1
+    ));
+}
@@ -0,0 +1,40 @@
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::{self, Write};
+
+use crate::Cli;
+
+/// Write the completion script for `shell` to `out`.
+pub fn generate(shell: Shell, out: &mut dyn Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Print the completion script for `shell`.
+pub fn print_completions(shell: Shell) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    generate(shell, &mut out);
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn every_shell_gets_a_script_naming_the_program() {
+        let name = Cli::command().get_name().to_string();
+        for shell in Shell::value_variants() {
+            let mut out = Vec::new();
+            generate(*shell, &mut out);
+            let script = String::from_utf8(out).expect("completion scripts are UTF-8");
+            assert!(!script.is_empty(), "{shell} script is empty");
+            assert!(
+                script.contains(&name),
+                "{shell} script does not mention {name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::Serialize;
+use similar::{ChangeTag, DiffTag, TextDiff};
+use std::{collections::BTreeMap, fs, io};
+use toml::{Table, Value};
+
+use crate::{existing_config_path, resolve_live_config, template, verify};
+
+/// What a line of a diff is.
+#[derive(Serialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum Op {
+    /// Only in the new file.
+    Add,
+    /// Only in the old file.
+    Del,
+    /// In both, shown for context.
+    Ctx,
+}
+
+/// A line of a diff, as printed by `diff --json`.
+#[derive(Serialize, JsonSchema)]
+pub struct Change {
+    op: Op,
+    /// 1-based line number in the old file for `del`, in the new file
+    /// otherwise.
+    line: usize,
+    content: String,
+}
+
+/// How `diff` prints its result.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DiffFormat {
+    /// A colored unified diff, for reading.
+    #[default]
+    Text,
+    /// Change records, as with `--json`.
+    Json,
+    /// A plain unified diff that `patch` and `git apply` accept.
+    Patch,
+}
+
+/// Print the differences between two stored configs in `format`.
+pub fn diff_configs(a: &str, b: &str, format: DiffFormat) -> io::Result<()> {
+    let old = fs::read_to_string(existing_config_path(a)?)?;
+    let new = fs::read_to_string(existing_config_path(b)?)?;
+    let files = (format!("{a}.toml"), format!("{b}.toml"));
+    output(&old, &new, (a, b), files, format)
+}
+
+/// Print the diff of `old` against `new`, labelled with their `names`, or
+/// for a patch with their `files` in the config directory.
+fn output(
+    old: &str,
+    new: &str,
+    names: (&str, &str),
+    files: (String, String),
+    format: DiffFormat,
+) -> io::Result<()> {
+    match format {
+        DiffFormat::Text => render(old, new, names.0, names.1),
+        DiffFormat::Json => {
+            let changes = changes(old, new);
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&changes).map_err(io::Error::other)?
+            );
+        }
+        DiffFormat::Patch => print!("{}", patch(old, new, &files.0, &files.1)),
+    }
+    Ok(())
+}
+
+/// A unified diff of `old` against `new` with `a/` and `b/` headers, as git
+/// writes them, and a marker where a file lacks its final newline. Empty when
+/// nothing changed, which is still a valid patch.
+pub fn patch(old: &str, new: &str, old_file: &str, new_file: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut unified = diff.unified_diff();
+    unified
+        .context_radius(3)
+        .header(&format!("a/{old_file}"), &format!("b/{new_file}"));
+    let patch = unified.to_string();
+    // Without hunks, similar still writes the header.
+    if unified.iter_hunks().next().is_none() {
+        return String::new();
+    }
+    patch
+}
+
+/// The changed lines of `old` against `new` with the same context as
+/// `render`, for tools drawing their own diff.
+pub fn changes(old: &str, new: &str) -> Vec<Change> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(3);
+
+    let mut changes = vec![];
+    for hunk in unified.iter_hunks() {
+        for change in hunk.iter_changes() {
+            let (op, index) = match change.tag() {
+                ChangeTag::Delete => (Op::Del, change.old_index()),
+                ChangeTag::Insert => (Op::Add, change.new_index()),
+                ChangeTag::Equal => (Op::Ctx, change.new_index()),
+            };
+            let value = change.value();
+            changes.push(Change {
+                op,
+                line: index.unwrap_or_default() + 1,
+                content: value.strip_suffix('\n').unwrap_or(value).to_string(),
+            });
+        }
+    }
+    changes
+}
+
+/// What a switch to `name` writes as the live config: the composed config
+/// for one with `extends`, includes or placeholders, otherwise the stored
+/// file.
+fn switched_content(name: &str) -> io::Result<String> {
+    let stored = fs::read_to_string(existing_config_path(name)?)?;
+    Ok(template::live_content(name)?.unwrap_or(stored))
+}
+
+/// Print what switching to `name` would change: the file actually linked as
+/// the live config against what the switch would write. The live file is
+/// read directly since it may have drifted from the recorded active config.
+pub fn diff_against_live(name: &str, format: DiffFormat) -> io::Result<()> {
+    let new = switched_content(name)?;
+    let live = resolve_live_config()?;
+    let old = match fs::read_to_string(&live) {
+        Ok(old) => old,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let live_file = live.file_name().unwrap_or_default().to_string_lossy();
+    let files = (live_file.to_string(), format!("{name}.toml"));
+    output(
+        &old,
+        &new,
+        (&live.display().to_string(), name),
+        files,
+        format,
+    )
+}
+
+/// Print the whole of `name` as a switch would write it, marking how each
+/// line differs from the live config: `+` for lines the live config lacks,
+/// `~` for lines that replace a live line, and `-` for live lines `name` does
+/// not have.
+pub fn show_against_live(name: &str) -> io::Result<()> {
+    let new = switched_content(name)?;
+    let live = resolve_live_config()?;
+    let old = match fs::read_to_string(&live) {
+        Ok(old) => old,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+
+    println!(
+        "{}",
+        format!("# {name} compared with {}", live.display()).bold()
+    );
+    let diff = TextDiff::from_lines(&old, &new);
+    for op in diff.ops() {
+        let replaced = op.tag() == DiffTag::Replace;
+        for change in diff.iter_changes(op) {
+            let value = change.value();
+            let value = value.strip_suffix('\n').unwrap_or(value);
+            match change.tag() {
+                ChangeTag::Delete => println!("{}", format!("-{value}").red()),
+                ChangeTag::Insert if replaced => println!("{}", format!("~{value}").yellow()),
+                ChangeTag::Insert => println!("{}", format!("+{value}").green()),
+                ChangeTag::Equal => println!(" {value}"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print a colored unified diff of `old` against `new`.
+pub fn render(old: &str, new: &str, old_name: &str, new_name: &str) {
+    let diff = TextDiff::from_lines(old, new);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(3);
+
+    let mut hunks = unified.iter_hunks().peekable();
+    if hunks.peek().is_none() {
+        println!("No differences between {old_name} and {new_name}");
+        return;
+    }
+
+    println!("{}", format!("--- {old_name}").bold());
+    println!("{}", format!("+++ {new_name}").bold());
+
+    for hunk in hunks {
+        println!("{}", hunk.header().cyan());
+        for change in hunk.iter_changes() {
+            let value = change.value();
+            let value = value.strip_suffix('\n').unwrap_or(value);
+            match change.tag() {
+                ChangeTag::Delete => println!("{}", format!("-{value}").red()),
+                ChangeTag::Insert => println!("{}", format!("+{value}").green()),
+                ChangeTag::Equal => println!(" {value}"),
+            }
+        }
+    }
+}
+
+/// Which side changed a key relative to the base, in a three-way diff.
+#[derive(Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Side {
+    /// Only A changed it.
+    A,
+    /// Only B changed it.
+    B,
+    /// A and B changed it the same way.
+    Both,
+    /// A and B changed it in different ways.
+    Conflict,
+}
+
+/// A key A or B changed relative to the base, as printed by
+/// `diff --base --json`. Values are in TOML, left out where the key is
+/// unset.
+#[derive(Serialize, JsonSchema)]
+pub struct KeyChange {
+    /// The dotted key, such as `build.jobs`.
+    key: String,
+    change: Side,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    a: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    b: Option<String>,
+}
+
+/// Every value in `table` by its key path, with arrays and scalars as
+/// leaves.
+fn leaves(table: &Table) -> BTreeMap<Vec<String>, Value> {
+    fn walk(path: &mut Vec<String>, table: &Table, out: &mut BTreeMap<Vec<String>, Value>) {
+        for (key, value) in table {
+            path.push(key.clone());
+            match value {
+                Value::Table(table) => walk(path, table, out),
+                value => drop(out.insert(path.clone(), value.clone())),
+            }
+            path.pop();
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(&mut vec![], table, &mut out);
+    out
+}
+
+/// `path` as a dotted key, quoting parts that are not bare keys, such as
+/// `target.'cfg(unix)'.runner`.
+fn dotted(path: &[String]) -> String {
+    path.iter()
+        .map(|part| {
+            let bare = !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if bare {
+                part.clone()
+            } else {
+                Value::String(part.clone()).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The keys `a` or `b` changed relative to `base`, sorted by key.
+fn key_changes(base: &Table, a: &Table, b: &Table) -> Vec<(Vec<String>, KeyChange)> {
+    let (base, a, b) = (leaves(base), leaves(a), leaves(b));
+    let mut paths = base
+        .keys()
+        .chain(a.keys())
+        .chain(b.keys())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (old, in_a, in_b) = (base.get(path), a.get(path), b.get(path));
+            let change = match (in_a == old, in_b == old) {
+                (true, true) => return None,
+                (false, true) => Side::A,
+                (true, false) => Side::B,
+                (false, false) if in_a == in_b => Side::Both,
+                (false, false) => Side::Conflict,
+            };
+            let change = KeyChange {
+                key: dotted(path),
+                change,
+                base: old.map(Value::to_string),
+                a: in_a.map(Value::to_string),
+                b: in_b.map(Value::to_string),
+            };
+            Some((path.clone(), change))
+        })
+        .collect()
+}
+
+/// Print how `a` and `b` each changed `base`, key by key and grouped by
+/// table, flagging keys they changed in different ways.
+pub fn diff_three_way(a: &str, b: &str, base: &str, format: DiffFormat) -> io::Result<()> {
+    if format == DiffFormat::Patch {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A three-way diff cannot be written as a patch, use --format text or json",
+        ));
+    }
+    let changes = key_changes(
+        &verify::parse_config(base)?,
+        &verify::parse_config(a)?,
+        &verify::parse_config(b)?,
+    );
+    if format == DiffFormat::Json {
+        let changes = changes
+            .into_iter()
+            .map(|(_, change)| change)
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&changes).map_err(io::Error::other)?
+        );
+        return Ok(());
+    }
+
+    if changes.is_empty() {
+        println!("Neither {a} nor {b} changes anything in {base}");
+        return Ok(());
+    }
+    println!("{}", format!("# {a} (A) and {b} (B) against {base}").bold());
+
+    let unset = "(unset)".to_string();
+    let mut sections = BTreeMap::<String, Vec<(&str, &KeyChange)>>::new();
+    for (path, change) in &changes {
+        let (key, table) = path.split_last().expect("key paths are never empty");
+        sections
+            .entry(dotted(table))
+            .or_default()
+            .push((key, change));
+    }
+    for (section, keys) in sections {
+        println!();
+        if !section.is_empty() {
+            println!("{}", format!("[{section}]").cyan());
+        }
+        for (key, change) in keys {
+            let key = dotted(&[key.to_string()]);
+            let old = change.base.as_ref().unwrap_or(&unset);
+            let in_a = change.a.as_ref().unwrap_or(&unset);
+            let in_b = change.b.as_ref().unwrap_or(&unset);
+            let line = match change.change {
+                Side::A => format!("A         {key}: {old} -> {in_a}")
+                    .green()
+                    .to_string(),
+                Side::B => format!("B         {key}: {old} -> {in_b}")
+                    .green()
+                    .to_string(),
+                Side::Both => format!("both      {key}: {old} -> {in_a}"),
+                Side::Conflict => format!("conflict  {key}: {old} -> A {in_a}, B {in_b}")
+                    .red()
+                    .to_string(),
+            };
+            println!("  {line}");
+        }
+    }
+
+    let count = |side| changes.iter().filter(|(_, c)| c.change == side).count();
+    println!();
+    println!(
+        "{} from A, {} from B, {} in both, {} conflict(s)",
+        count(Side::A),
+        count(Side::B),
+        count(Side::Both),
+        count(Side::Conflict)
+    );
+    Ok(())
+}
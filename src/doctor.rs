@@ -0,0 +1,196 @@
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+use crate::{
+    can_prompt, config_path, current_config,
+    links::{self, LiveState},
+    resolve_live_config, switch_config, template, warning, SwitchOptions,
+};
+
+/// What to do with a live config that is no longer linked to its config.
+enum Repair {
+    /// Copy the live content into the stored config, then relink it.
+    KeepLive,
+    /// Relink the stored config, dropping the live content.
+    DiscardLive,
+}
+
+/// How a doctor check came out.
+#[derive(Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// One doctor check, as printed by `doctor --json`.
+#[derive(Serialize, JsonSchema)]
+pub struct Check {
+    /// Stable identifier of the check, such as `live-link`.
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+/// What the checks found about the active config, for `--fix`.
+struct Findings {
+    checks: Vec<Check>,
+    /// The active config and the state of its live link, if both are known.
+    live: Option<(String, LiveState)>,
+}
+
+fn run_checks() -> io::Result<Findings> {
+    let mut checks = vec![];
+    let check = |name, status, detail: String| Check {
+        name,
+        status,
+        detail,
+    };
+
+    let Some(current) = current_config()? else {
+        checks.push(check(
+            "active-config",
+            Status::Ok,
+            "No config is active".into(),
+        ));
+        return Ok(Findings { checks, live: None });
+    };
+
+    let stored = config_path(&current)?;
+    if !stored.is_file() {
+        checks.push(check(
+            "active-config",
+            Status::Fail,
+            format!("cargo-config-current names {current}, which does not exist"),
+        ));
+        return Ok(Findings { checks, live: None });
+    }
+    checks.push(check(
+        "active-config",
+        Status::Ok,
+        format!("{current} is active"),
+    ));
+
+    let live = resolve_live_config()?;
+    let state = links::live_state(&current, &stored, &live)?;
+    checks.push(match state.problem() {
+        None => check(
+            "live-link",
+            Status::Ok,
+            format!("{} is linked to {current}", live.display()),
+        ),
+        Some(problem) => check(
+            "live-link",
+            match state {
+                LiveState::Missing => Status::Fail,
+                _ => Status::Warn,
+            },
+            format!(
+                "{} {problem}, so edits to it no longer reach {current}",
+                live.display()
+            ),
+        ),
+    });
+    Ok(Findings {
+        checks,
+        live: Some((current, state)),
+    })
+}
+
+/// The doctor checks alone, without printing or repairing anything, for the
+/// `--report-file` bundle.
+pub fn checks() -> io::Result<Vec<Check>> {
+    Ok(run_checks()?.checks)
+}
+
+/// Check the tool's state for common problems, most importantly a live config
+/// that an editor rewrote as an independent file. With `fix`, offer to repair
+/// what can be repaired. With `json`, print the checks as JSON instead.
+pub fn doctor(fix: bool, json: bool) -> io::Result<()> {
+    let Findings { checks, live } = run_checks()?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&checks).map_err(io::Error::other)?
+        );
+        return Ok(());
+    }
+
+    for check in &checks {
+        match check.status {
+            Status::Ok => println!("Success:   {}  {}", "✓".green(), check.detail),
+            Status::Warn | Status::Fail => warning::print(&check.detail),
+        }
+    }
+
+    let Some((current, state)) = live.filter(|(_, state)| state.problem().is_some()) else {
+        return Ok(());
+    };
+    if !fix {
+        return Ok(());
+    }
+
+    let stored = config_path(&current)?;
+    let live = resolve_live_config()?;
+    // Content written from an `extends` chain or a template cannot be copied
+    // back into a single config, and a missing file has nothing to keep.
+    let keepable =
+        matches!(state, LiveState::Drifted) && template::live_content(&current)?.is_none();
+    let repair = if keepable {
+        ask_repair(&current)?
+    } else {
+        Repair::DiscardLive
+    };
+
+    if let Repair::KeepLive = repair {
+        tracing::debug!(from = %live.display(), to = %stored.display(), "keeping live edits");
+        fs::write(&stored, fs::read(&live)?)?;
+    }
+    switch_config(
+        &current,
+        &SwitchOptions {
+            keep_previous: true,
+            ..SwitchOptions::default()
+        },
+    )?;
+
+    match repair {
+        Repair::KeepLive => println!(
+            "Success:   {}  Saved the live edits to {current} and relinked it",
+            "✓".green()
+        ),
+        Repair::DiscardLive => println!("Success:   {}  Relinked {current}", "✓".green()),
+    }
+    Ok(())
+}
+
+fn ask_repair(name: &str) -> io::Result<Repair> {
+    if !can_prompt() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "stdin is not a terminal to ask whether to keep the live edits",
+        ));
+    }
+
+    println!("  k) keep the live edits, copying them into {name}");
+    println!("  d) discard the live edits, relinking {name} as stored");
+    print!("Repair how? [k/d, anything else aborts] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    match answer.trim() {
+        "k" | "K" => Ok(Repair::KeepLive),
+        "d" | "D" => Ok(Repair::DiscardLive),
+        _ => Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "Left the live config as it is, aborting repair",
+        )),
+    }
+}
@@ -0,0 +1,58 @@
+use clap::ValueEnum;
+use std::{io, path::Path};
+
+/// A format configs can be read from and written to. Configs are always
+/// stored as TOML, these only matter at the edges.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Format::Toml => "TOML",
+            Format::Json => "JSON",
+            Format::Yaml => "YAML",
+        }
+    }
+
+    /// Guess the format from a file extension, falling back to TOML.
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Format::Json,
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Toml,
+        }
+    }
+}
+
+/// Parse `content` written in `format` into a TOML table. `source` names the
+/// input in errors.
+pub fn parse(content: &str, format: Format, source: &str) -> io::Result<toml::Table> {
+    let parsed = match format {
+        Format::Toml => toml::from_str(content).map_err(|err| err.to_string()),
+        Format::Json => serde_json::from_str(content).map_err(|err| err.to_string()),
+        Format::Yaml => serde_yaml::from_str(content).map_err(|err| err.to_string()),
+    };
+
+    parsed.map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{source} is not valid {}: {err}", format.name()),
+        )
+    })
+}
+
+pub fn serialize(table: &toml::Table, format: Format) -> io::Result<String> {
+    match format {
+        Format::Toml => toml::to_string(table).map_err(io::Error::other),
+        Format::Json => serde_json::to_string_pretty(table)
+            .map(|json| json + "\n")
+            .map_err(io::Error::other),
+        Format::Yaml => serde_yaml::to_string(table).map_err(io::Error::other),
+    }
+}
@@ -0,0 +1,67 @@
+use owo_colors::OwoColorize;
+use std::{fs, io, path::PathBuf};
+
+use crate::{config_path, merge::deep_merge, resolve_config_dir, verify};
+
+/// Directory holding the fragments a config is assembled from,
+/// `{name}.d/` next to the config itself.
+pub fn fragment_dir(name: &str) -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push(format!("{name}.d"));
+    Ok(path)
+}
+
+pub fn has_fragments(name: &str) -> io::Result<bool> {
+    Ok(fragment_dir(name)?.is_dir())
+}
+
+/// Merge every `*.toml` fragment in `{name}.d/`, in file name order, into
+/// `{name}.toml`. Later fragments win over earlier ones.
+pub fn assemble(name: &str) -> io::Result<()> {
+    let dir = fragment_dir(name)?;
+    let mut fragments = fs::read_dir(&dir)
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("{name} has no fragment directory at {}", dir.display()),
+            )
+        })?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    fragments.sort();
+
+    if fragments.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} contains no .toml fragments", dir.display()),
+        ));
+    }
+
+    let mut assembled = toml::Table::new();
+    for path in &fragments {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let fragment = fs::read_to_string(path)?
+            .parse::<toml::Table>()
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Fragment {file_name} of {name} is not valid TOML: {err}"),
+                )
+            })?;
+
+        let keys = fragment.keys().cloned().collect::<Vec<_>>().join(", ");
+        println!("Fragment:  {}  {file_name} ({keys})", "+".cyan());
+        deep_merge(&mut assembled, fragment);
+    }
+
+    let mut content = format!(
+        "# Assembled from {name}.d/ by cargo-config-profiles, edit the fragments instead\n"
+    );
+    content.push_str(&toml::to_string(&assembled).map_err(io::Error::other)?);
+
+    tracing::debug!(name, fragments = fragments.len(), "assembled config");
+    fs::write(config_path(name)?, content)?;
+    verify::parse_config(name).map(|_| ())
+}
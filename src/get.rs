@@ -0,0 +1,102 @@
+use std::{fs, io, path::PathBuf};
+use toml::{Table, Value};
+
+use crate::{current_config, inherit, merge, project_dir, resolve_live_config, verify};
+
+/// The files cargo reads config from, lowest precedence first: the live
+/// config in the cargo home, then `.cargo/config.toml` (or the older
+/// `.cargo/config`) in each directory from the filesystem root down to the
+/// working directory, or `--cwd`. A file reached twice, such as the home's own config
+/// when working under the home directory, is only read once.
+pub fn config_files() -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let live = resolve_live_config()?;
+    if live.exists() {
+        files.push(live);
+    }
+
+    let cwd = project_dir()?;
+    let mut project = vec![];
+    for dir in cwd.ancestors() {
+        let found = ["config.toml", "config"]
+            .into_iter()
+            .map(|file| dir.join(".cargo").join(file))
+            .find(|path| path.is_file());
+        project.extend(found);
+    }
+
+    for path in project.into_iter().rev() {
+        let canonical = fs::canonicalize(&path)?;
+        let seen = files
+            .iter()
+            .any(|file| fs::canonicalize(file).is_ok_and(|file| file == canonical));
+        if !seen {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+pub fn read(path: &PathBuf) -> io::Result<Table> {
+    fs::read_to_string(path)?.parse::<Table>().map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not valid TOML: {err}", path.display()),
+        )
+    })
+}
+
+/// Look up the dotted `key` in `table`.
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Print the value of the dotted `key` in the active config. With
+/// `effective`, print what cargo resolves instead: the live config merged
+/// beneath every project config from the working directory up, with a
+/// `CARGO_*` environment variable taking precedence over all of them.
+pub fn get(key: &str, effective: bool) -> io::Result<()> {
+    let not_set = |scope: &str| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{key} is not set in {scope}"),
+        )
+    };
+
+    let (table, scope) = if effective {
+        if let Some((var, value)) = verify::env_override(key) {
+            tracing::debug!(key, var, "key set by environment variable");
+            println!("{value}");
+            return Ok(());
+        }
+
+        let mut merged = Table::new();
+        for path in config_files()? {
+            tracing::debug!(path = %path.display(), "merging config file");
+            merge::deep_merge(&mut merged, read(&path)?);
+        }
+        (merged, "any config cargo reads".to_string())
+    } else {
+        let name = current_config()?.ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No config is active, pass --effective to read cargo's config files",
+        ))?;
+        let table = match inherit::resolve(&name)? {
+            Some(table) => table,
+            None => verify::parse_config(&name)?,
+        };
+        (table, name)
+    };
+
+    match lookup(&table, key).ok_or_else(|| not_set(&scope))? {
+        Value::String(value) => println!("{value}"),
+        Value::Table(table) => print!("{}", toml::to_string(table).map_err(io::Error::other)?),
+        value => println!("{value}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf, time::SystemTime};
+
+use crate::resolve_config_dir;
+
+fn previous_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-previous");
+    Ok(path)
+}
+
+fn last_active_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-last-active");
+    Ok(path)
+}
+
+/// When each config stopped being active, as recorded by [`record_left`].
+/// Entries that do not parse are left out.
+pub fn last_active() -> io::Result<BTreeMap<String, SystemTime>> {
+    let content = match fs::read_to_string(last_active_path()?) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err),
+    };
+    let times: BTreeMap<String, String> =
+        toml::from_str(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(times
+        .into_iter()
+        .filter_map(|(name, time)| Some((name, humantime::parse_rfc3339(&time).ok()?)))
+        .collect())
+}
+
+/// Write `times` as the record [`last_active`] reads.
+pub fn save_last_active(times: &BTreeMap<String, SystemTime>) -> io::Result<()> {
+    let times = times
+        .iter()
+        .map(|(name, time)| (name, humantime::format_rfc3339_nanos(*time).to_string()))
+        .collect::<BTreeMap<_, _>>();
+    fs::write(
+        last_active_path()?,
+        toml::to_string(&times).map_err(io::Error::other)?,
+    )
+}
+
+/// Record that `name` stopped being active just now, switched away from.
+pub fn record_left(name: &str) -> io::Result<()> {
+    let mut times = last_active()?;
+    times.insert(name.to_string(), SystemTime::now());
+    tracing::debug!(name, "recording when the config was last active");
+    save_last_active(&times)
+}
+
+/// The config that was active before the current one, if recorded.
+pub fn previous() -> io::Result<Option<String>> {
+    match fs::read_to_string(previous_path()?) {
+        Ok(previous) if !previous.is_empty() => Ok(Some(previous)),
+        Ok(_) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The recorded previous config, or an error saying there is none.
+pub fn require_previous() -> io::Result<String> {
+    previous()?.ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        "No previous config is recorded, switch to another config first",
+    ))
+}
+
+pub fn set_previous(name: &str) -> io::Result<()> {
+    tracing::debug!(name, "recording previous config");
+    fs::write(previous_path()?, name)
+}
+
+pub fn clear_previous() -> io::Result<()> {
+    match fs::remove_file(previous_path()?) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,70 @@
+use std::{io, path::PathBuf, process::Command};
+
+use crate::{resolve_config_dir, settings};
+
+/// Points around a switch where a user script can run.
+#[derive(Clone, Copy, Debug)]
+pub enum Hook {
+    PreSwitch,
+    PostSwitch,
+}
+
+impl Hook {
+    fn name(self) -> &'static str {
+        match self {
+            Hook::PreSwitch => "pre-switch",
+            Hook::PostSwitch => "post-switch",
+        }
+    }
+}
+
+/// The script for `hook`: the path set in settings if any, otherwise
+/// `hooks/<hook name>` in the config directory if it exists.
+fn hook_path(hook: Hook) -> io::Result<Option<PathBuf>> {
+    let settings = settings::load()?;
+    let configured = match hook {
+        Hook::PreSwitch => settings.pre_switch_hook,
+        Hook::PostSwitch => settings.post_switch_hook,
+    };
+    if configured.is_some() {
+        return Ok(configured);
+    }
+
+    let mut path = resolve_config_dir()?;
+    path.push("hooks");
+    path.push(hook.name());
+    Ok(path.is_file().then_some(path))
+}
+
+/// Run the script for `hook`, if there is one, with the previously active and
+/// the new config names as arguments. The previous name is empty if no config
+/// was active.
+pub fn run_hook(hook: Hook, previous: Option<&str>, next: &str) -> io::Result<()> {
+    let Some(path) = hook_path(hook)? else {
+        return Ok(());
+    };
+
+    tracing::debug!(hook = hook.name(), path = %path.display(), "running hook");
+    let status = Command::new(&path)
+        .args([previous.unwrap_or_default(), next])
+        .status()
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!(
+                    "Could not run {} hook {}: {err}",
+                    hook.name(),
+                    path.display()
+                ),
+            )
+        })?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} hook {} failed with {status}",
+            hook.name(),
+            path.display()
+        )));
+    }
+    Ok(())
+}
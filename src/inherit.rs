@@ -0,0 +1,179 @@
+use std::{collections::BTreeMap, io};
+
+use crate::{merge::deep_merge, meta, verify};
+
+/// Key naming the config a config builds on.
+pub const EXTENDS_KEY: &str = "extends";
+
+/// Key listing the fragments merged in beneath a config.
+pub const INCLUDE_KEY: &str = "include";
+
+/// The config `table` extends, if it names one.
+pub fn base_of(table: &toml::Table) -> Option<&str> {
+    table.get(EXTENDS_KEY)?.as_str()
+}
+
+/// The base of every config in `names` that has one. Configs that cannot be
+/// parsed are left out, they cannot extend anything.
+pub fn bases(names: &[String]) -> io::Result<BTreeMap<String, String>> {
+    let mut bases = BTreeMap::new();
+    for name in names {
+        match verify::parse_config(name) {
+            Ok(table) => {
+                if let Some(base) = base_of(&table) {
+                    bases.insert(name.clone(), base.to_string());
+                }
+            }
+            Err(err) => tracing::debug!(name, "skipping unparsable config: {err}"),
+        }
+    }
+    Ok(bases)
+}
+
+/// Follow the `extends` chain from `name` in `bases`, returning the configs
+/// that form a cycle if there is one.
+pub fn find_cycle(name: &str, bases: &BTreeMap<String, String>) -> Option<Vec<String>> {
+    let mut chain = vec![name.to_string()];
+    let mut current = name;
+    while let Some(base) = bases.get(current) {
+        if let Some(start) = chain.iter().position(|seen| seen == base) {
+            let mut cycle = chain.split_off(start);
+            cycle.push(base.clone());
+            return Some(cycle);
+        }
+        chain.push(base.clone());
+        current = base;
+    }
+    None
+}
+
+/// Take the `include` list out of `table`, loading each fragment it names.
+fn take_includes(name: &str, table: &mut toml::Table) -> io::Result<Vec<toml::Table>> {
+    let Some(include) = table.remove(INCLUDE_KEY) else {
+        return Ok(vec![]);
+    };
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let fragments = include.as_array().ok_or_else(|| {
+        invalid(format!(
+            "include in {name} must be a list of fragment names"
+        ))
+    })?;
+
+    let mut tables = vec![];
+    for fragment in fragments {
+        let fragment = fragment.as_str().ok_or_else(|| {
+            invalid(format!(
+                "include in {name} must be a list of fragment names"
+            ))
+        })?;
+        if !meta::get(fragment)?.fragment {
+            return Err(invalid(format!(
+                "{name} includes {fragment}, which is not a fragment. Mark it with `fragment {fragment}`"
+            )));
+        }
+
+        let table = verify::parse_config(fragment)?;
+        // Fragments are merged as they are, they do not compose further.
+        if table.contains_key(INCLUDE_KEY) || table.contains_key(EXTENDS_KEY) {
+            return Err(invalid(format!(
+                "Fragment {fragment} cannot use include or extends itself"
+            )));
+        }
+        tables.push(table);
+    }
+    Ok(tables)
+}
+
+/// `name` merged over everything it extends, base first, without the
+/// `extends` keys. Each config's `include`d fragments are merged in just
+/// beneath it, in the order listed. The chain is walked iteratively and a
+/// config seen twice is reported as a cycle, so `a extends b` and `b extends
+/// a` is an error rather than endless recursion. Returns `None` if `name`
+/// neither extends nor includes anything, or is not valid TOML.
+pub fn resolve(name: &str) -> io::Result<Option<toml::Table>> {
+    let mut chain = vec![];
+    let mut tables = vec![];
+    let mut included = false;
+    let mut current = name.to_string();
+
+    loop {
+        if chain.contains(&current) {
+            chain.push(current);
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Inheritance cycle {}", chain.join(" -> ")),
+            ));
+        }
+
+        let mut table = match verify::parse_config(&current) {
+            Ok(table) => table,
+            // A config that does not parse extends nothing cargo could see,
+            // so it is linked as it is and cargo reports the error.
+            Err(err) if chain.is_empty() && err.kind() == io::ErrorKind::InvalidData => {
+                return Ok(None)
+            }
+            Err(err) => return Err(err),
+        };
+        let base = base_of(&table).map(str::to_string);
+        table.remove(EXTENDS_KEY);
+        let includes = take_includes(&current, &mut table)?;
+        included |= !includes.is_empty();
+        tables.push((includes, table));
+        chain.push(current);
+
+        match base {
+            Some(base) => current = base,
+            None => break,
+        }
+    }
+
+    if tables.len() == 1 && !included {
+        return Ok(None);
+    }
+
+    let mut resolved = toml::Table::new();
+    for (includes, table) in tables.into_iter().rev() {
+        for include in includes {
+            deep_merge(&mut resolved, include);
+        }
+        deep_merge(&mut resolved, table);
+    }
+    tracing::debug!(name, chain = chain.join(" -> "), "resolved inheritance");
+    Ok(Some(resolved))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, base)| (name.to_string(), base.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn two_configs_extending_each_other_are_a_cycle() {
+        let bases = bases(&[("a", "b"), ("b", "a")]);
+        assert_eq!(
+            find_cycle("a", &bases),
+            Some(vec!["a".to_string(), "b".to_string(), "a".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_config_extending_itself_is_a_cycle() {
+        let bases = bases(&[("a", "a")]);
+        assert_eq!(
+            find_cycle("a", &bases),
+            Some(vec!["a".to_string(), "a".to_string()])
+        );
+    }
+
+    #[test]
+    fn a_chain_ending_in_a_root_has_no_cycle() {
+        let bases = bases(&[("a", "b"), ("b", "c")]);
+        assert_eq!(find_cycle("a", &bases), None);
+    }
+}
@@ -0,0 +1,330 @@
+use owo_colors::OwoColorize;
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::Path,
+};
+
+use crate::{
+    current_config, list, project, resolve_config_dir, resolve_live_config, switch_config,
+    template, warning, SwitchOptions,
+};
+
+/// How the live config relates to the stored config it should be.
+pub enum LiveState {
+    /// The live config is the stored file, through a hard link or a symlink.
+    Linked,
+    /// The live config was written from an `extends` chain or rendered from a
+    /// template, and still matches.
+    Resolved,
+    /// The live config has the stored content but is a separate file, so
+    /// edits to one no longer reach the other.
+    Copy,
+    /// The live config differs from the stored config.
+    Drifted,
+    Missing,
+}
+
+impl LiveState {
+    /// What is wrong with the live config, if anything.
+    pub fn problem(&self) -> Option<&'static str> {
+        match self {
+            LiveState::Linked | LiveState::Resolved => None,
+            LiveState::Copy => Some("is a copy, not a link"),
+            LiveState::Drifted => Some("has different contents"),
+            LiveState::Missing => Some("is missing"),
+        }
+    }
+}
+
+/// Whether `name` is the active config and the live config is still linked
+/// to it, so switching to it again would change nothing.
+pub fn is_active(name: &str) -> io::Result<bool> {
+    if current_config()?.as_deref() != Some(name) {
+        return Ok(false);
+    }
+    let mut stored = resolve_config_dir()?;
+    stored.push(format!("{name}.toml"));
+    Ok(live_state(name, &stored, &resolve_live_config()?)?
+        .problem()
+        .is_none())
+}
+
+/// Succeed only if `name` is the active config and the live config is still
+/// linked to it, for scripts deciding whether to switch.
+pub fn check_active(name: &str) -> io::Result<()> {
+    let live = resolve_live_config()?;
+    let current = current_config()?;
+    if current.as_deref() != Some(name) {
+        let state = match current {
+            Some(current) => format!("{current} is active"),
+            None => "no config is active".to_string(),
+        };
+        return Err(io::Error::other(format!(
+            "{name} is not the active config, {state}"
+        )));
+    }
+
+    let mut stored = resolve_config_dir()?;
+    stored.push(format!("{name}.toml"));
+    if let Some(problem) = live_state(name, &stored, &live)?.problem() {
+        return Err(io::Error::other(format!(
+            "{name} is active but {} {problem}",
+            live.display()
+        )));
+    }
+    println!(
+        "Success:   {}  {name} is active and linked to {}",
+        "✓".green(),
+        live.display()
+    );
+    Ok(())
+}
+
+/// Re-establish the live config from the active config, for when it was
+/// deleted or replaced by a copy. A live config with different contents is
+/// only replaced with `force`, since its edits would be lost.
+pub fn repair_link(force: bool) -> io::Result<()> {
+    let live = resolve_live_config()?;
+    let current = current_config()?.ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        "No config is active, switch to one instead",
+    ))?;
+    let mut stored = resolve_config_dir()?;
+    stored.push(format!("{current}.toml"));
+
+    let state = live_state(&current, &stored, &live)?;
+    let Some(problem) = state.problem() else {
+        println!(
+            "Success:   {}  {} is already linked to {current}",
+            "✓".green(),
+            live.display()
+        );
+        return Ok(());
+    };
+    if matches!(state, LiveState::Drifted) && !force {
+        return Err(io::Error::other(format!(
+            "{} {problem}, rerun with --force to discard them or use `doctor --fix` to keep them",
+            live.display()
+        )));
+    }
+
+    switch_config(
+        &current,
+        &SwitchOptions {
+            keep_previous: true,
+            ..SwitchOptions::default()
+        },
+    )?;
+    println!(
+        "Success:   {}  {} {problem}, relinked it to {current}",
+        "✓".green(),
+        live.display()
+    );
+    Ok(())
+}
+
+/// Recover after the cargo home was moved, copied or recreated, which
+/// breaks every hard link at once: check the config directory is in place,
+/// then relink the live config as [`repair_link`] does and every broken
+/// project link, reporting each. Carries on past a link it cannot repair and
+/// fails at the end.
+pub fn repair_all(force: bool) -> io::Result<()> {
+    // Created when missing, as by every command.
+    let dir = resolve_config_dir()?;
+    println!(
+        "Success:   {}  Config directory {} is in place",
+        "✓".green(),
+        dir.display()
+    );
+
+    let live = if current_config()?.is_some() {
+        repair_link(force)
+    } else {
+        println!("No config is active, so there is no live config to relink");
+        Ok(())
+    };
+    let left_broken = project::repair_links(force)?;
+    live?;
+    if left_broken > 0 {
+        return Err(io::Error::other(format!(
+            "{left_broken} project link(s) were left broken"
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the live config is still linked to the active config, and
+/// report stored configs that are hard-linked from somewhere else. With
+/// `repair`, a broken live link is replaced by switching to the active config
+/// again.
+pub fn verify_links(repair: bool) -> io::Result<()> {
+    let live = resolve_live_config()?;
+    let mut broken = 0;
+
+    match current_config()? {
+        Some(current) => {
+            let mut stored = resolve_config_dir()?;
+            stored.push(format!("{current}.toml"));
+
+            match live_state(&current, &stored, &live)?.problem() {
+                None => println!(
+                    "Success:   {}  {} is linked to {current}",
+                    "✓".green(),
+                    live.display()
+                ),
+                Some(description) if repair => {
+                    switch_config(
+                        &current,
+                        &SwitchOptions {
+                            keep_previous: true,
+                            ..SwitchOptions::default()
+                        },
+                    )?;
+                    println!(
+                        "Success:   {}  {} {description}, relinked it to {current}",
+                        "✓".green(),
+                        live.display()
+                    );
+                }
+                Some(description) => {
+                    warning::print(format!(
+                        "{} {description}, expected a link to {current}",
+                        live.display()
+                    ));
+                    broken += 1;
+                }
+            }
+        }
+        None => warning::print(format!(
+            "No config is active, so {} is not checked",
+            live.display()
+        )),
+    }
+
+    let live_metadata = fs::metadata(&live).ok();
+    for entry in list::entries(false)? {
+        let mut path = resolve_config_dir()?;
+        path.push(format!("{}.toml", entry.name));
+        let metadata = fs::metadata(&path)?;
+
+        let links = link_count(&metadata);
+        let expected = match &live_metadata {
+            Some(live_metadata) if same_file(&metadata, live_metadata) => 2,
+            _ => 1,
+        };
+        if links > expected {
+            warning::print(format!(
+                "{} has extra hard links ({}) outside the live config, an old \
+                 link may be left behind",
+                entry.name,
+                links - expected
+            ));
+        }
+    }
+
+    if broken > 0 {
+        return Err(io::Error::other(
+            "The live config is not linked to the active config, rerun with --repair",
+        ));
+    }
+    Ok(())
+}
+
+/// Why `entry` looks stale, if it does:
+///
+/// - the active config, when the live config is no longer linked to it (a
+///   copy, changed or missing);
+/// - another config with the same content as the live config, a duplicate
+///   that is probably left over;
+/// - another config with hard links besides the live config and `link`
+///   project links, likely an old live link left behind after a switch
+///   elsewhere.
+pub fn stale_reason(entry: &list::ListEntry) -> io::Result<Option<&'static str>> {
+    let live = resolve_live_config()?;
+    let mut stored = resolve_config_dir()?;
+    stored.push(format!("{}.toml", entry.name));
+
+    if entry.active {
+        return Ok(live_state(&entry.name, &stored, &live)?
+            .problem()
+            .map(|_| "active, but its link is broken"));
+    }
+
+    let metadata = fs::metadata(&stored)?;
+    let live_metadata = fs::metadata(&live).ok();
+    if live_metadata
+        .as_ref()
+        .is_some_and(|live_metadata| !same_file(&metadata, live_metadata))
+        && fs::read(&stored)? == fs::read(&live)?
+    {
+        return Ok(Some("same content as the live config"));
+    }
+
+    let project_links = project::load()?
+        .keys()
+        .filter_map(|path| fs::symlink_metadata(path).ok())
+        .filter(|link| same_file(&metadata, link))
+        .count() as u64;
+    let live_links =
+        u64::from(live_metadata.is_some_and(|live_metadata| same_file(&metadata, &live_metadata)));
+    if link_count(&metadata) > 1 + project_links + live_links {
+        return Ok(Some(
+            "hard-linked from elsewhere, an old link may be left behind",
+        ));
+    }
+    Ok(None)
+}
+
+/// How the live config at `live` relates to `name`, stored at `stored`.
+pub fn live_state(name: &str, stored: &Path, live: &Path) -> io::Result<LiveState> {
+    let live_metadata = match fs::metadata(live) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(LiveState::Missing),
+        Err(err) => return Err(err),
+    };
+
+    if same_file(&fs::metadata(stored)?, &live_metadata) {
+        return Ok(LiveState::Linked);
+    }
+
+    let content = fs::read_to_string(live)?;
+    if let Some(expected) = template::live_content(name)? {
+        return Ok(if content == expected {
+            LiveState::Resolved
+        } else {
+            LiveState::Drifted
+        });
+    }
+
+    Ok(if content == fs::read_to_string(stored)? {
+        LiveState::Copy
+    } else {
+        LiveState::Drifted
+    })
+}
+
+/// Whether two files are the same file, as hard links or through a symlink.
+#[cfg(unix)]
+pub fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+// Without inodes a link cannot be told from a copy, so files are only ever
+// reported as copies.
+#[cfg(not(unix))]
+pub fn same_file(_: &Metadata, _: &Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn link_count(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink()
+}
+
+#[cfg(not(unix))]
+fn link_count(_: &Metadata) -> u64 {
+    1
+}
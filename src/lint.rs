@@ -0,0 +1,227 @@
+use owo_colors::OwoColorize;
+use std::io;
+
+use crate::{compat, config_names, current_config, parallel, verify::parse_config, warning};
+
+/// Top-level tables and keys cargo reads from a config file.
+pub const TOP_LEVEL: &[&str] = &[
+    "alias",
+    "build",
+    "cache",
+    "cargo-new",
+    "credential-alias",
+    "doc",
+    "env",
+    "future-incompat-report",
+    "gc",
+    "host",
+    "http",
+    "install",
+    "net",
+    "patch",
+    "paths",
+    "profile",
+    "registries",
+    "registry",
+    "resolver",
+    "source",
+    "target",
+    "term",
+    "unstable",
+];
+
+/// Keys of the tables whose contents cargo defines. Tables missing here, like
+/// `alias` or `env`, take arbitrary keys.
+const TABLE_KEYS: &[(&str, &[&str])] = &[
+    (
+        "build",
+        &[
+            "build-dir",
+            "dep-info-basedir",
+            "incremental",
+            "jobs",
+            "pipelining",
+            "rustc",
+            "rustc-workspace-wrapper",
+            "rustc-wrapper",
+            "rustdoc",
+            "rustdocflags",
+            "rustflags",
+            "target",
+            "target-dir",
+        ],
+    ),
+    ("cache", &["auto-clean-frequency"]),
+    ("cargo-new", &["vcs"]),
+    ("doc", &["browser"]),
+    ("future-incompat-report", &["frequency"]),
+    (
+        "http",
+        &[
+            "cainfo",
+            "check-revoke",
+            "debug",
+            "low-speed-limit",
+            "multiplexing",
+            "proxy",
+            "proxy-cainfo",
+            "ssl-version",
+            "timeout",
+            "user-agent",
+        ],
+    ),
+    ("install", &["root"]),
+    ("net", &["git-fetch-with-cli", "offline", "retry", "ssh"]),
+    (
+        "registry",
+        &[
+            "credential-provider",
+            "default",
+            "global-credential-providers",
+            "index",
+            "token",
+        ],
+    ),
+    ("resolver", &["incompatible-rust-versions"]),
+    (
+        "term",
+        &[
+            "color",
+            "hyperlinks",
+            "progress",
+            "quiet",
+            "unicode",
+            "verbose",
+        ],
+    ),
+];
+
+/// A key cargo does not know, with the known key it is probably a typo of.
+struct Unknown {
+    path: String,
+    suggestion: Option<&'static str>,
+}
+
+/// Warn about keys cargo does not know, which it silently ignores, and keys
+/// the installed cargo is too old for. Cargo adds keys over time, so these
+/// are only ever warnings.
+pub fn lint_config(name: Option<&str>) -> io::Result<()> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => current_config()?.ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No config is active, pass a config name to lint",
+        ))?,
+    };
+
+    let table = parse_config(&name)?;
+    let unknown = unknown_keys(&table);
+    if unknown.is_empty() {
+        println!("Success:   {}  No unknown keys in {name}", "✓".green());
+    }
+    for unknown in unknown {
+        warn_unknown(None, &unknown);
+    }
+    if let Some(cargo) = compat::cargo_version() {
+        for too_new in compat::too_new(&table, cargo) {
+            compat::warn_too_new(None, &too_new, cargo);
+        }
+    }
+    Ok(())
+}
+
+/// Lint every config, `jobs` at a time, printing the results in name order
+/// once all are done. Configs that do not parse are reported and skipped.
+pub fn lint_all(jobs: usize) -> io::Result<()> {
+    let names = config_names()?;
+    let cargo = compat::cargo_version();
+    let results = parallel::map(&names, jobs, |name| {
+        parse_config(name).map(|table| {
+            let too_new = cargo.map_or_else(Vec::new, |cargo| compat::too_new(&table, cargo));
+            (unknown_keys(&table), too_new)
+        })
+    });
+
+    let mut clean = 0;
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok((unknown, too_new)) => {
+                if unknown.is_empty() {
+                    clean += 1;
+                }
+                for unknown in unknown {
+                    warn_unknown(Some(name), &unknown);
+                }
+                for too_new in too_new {
+                    // Only found when the version is known.
+                    compat::warn_too_new(Some(name), &too_new, cargo.unwrap_or_default());
+                }
+            }
+            Err(err) => warning::print(err),
+        }
+    }
+    println!(
+        "Success:   {}  No unknown keys in {clean} of {} configs",
+        "✓".green(),
+        names.len()
+    );
+    Ok(())
+}
+
+fn unknown_keys(table: &toml::Table) -> Vec<Unknown> {
+    let mut unknown = vec![];
+
+    for (key, value) in table {
+        if !TOP_LEVEL.contains(&key.as_str()) {
+            unknown.push(Unknown {
+                path: key.clone(),
+                suggestion: suggest(key, TOP_LEVEL),
+            });
+            continue;
+        }
+
+        let (Some(known), Some(table)) = (table_keys(key), value.as_table()) else {
+            continue;
+        };
+        for child in table.keys() {
+            if !known.contains(&child.as_str()) {
+                unknown.push(Unknown {
+                    path: format!("{key}.{child}"),
+                    suggestion: suggest(child, known),
+                });
+            }
+        }
+    }
+    unknown
+}
+
+fn table_keys(table: &str) -> Option<&'static [&'static str]> {
+    TABLE_KEYS
+        .iter()
+        .find(|(name, _)| *name == table)
+        .map(|(_, keys)| *keys)
+}
+
+fn warn_unknown(name: Option<&str>, unknown: &Unknown) {
+    let path = match name {
+        Some(name) => format!("{} in {name}", unknown.path),
+        None => unknown.path.clone(),
+    };
+    match unknown.suggestion {
+        Some(suggestion) => {
+            warning::print(format!("Unknown key {path}, did you mean {suggestion}?"))
+        }
+        None => warning::print(format!("Unknown key {path}")),
+    }
+}
+
+/// The known key closest to `key`, if any is close enough to be a typo.
+fn suggest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    let threshold = (key.len() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (strsim::osa_distance(key, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
@@ -0,0 +1,481 @@
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, BufWriter, Write},
+    time::SystemTime,
+};
+
+use crate::{
+    current_config, history, inherit,
+    links::{self, LiveState},
+    meta::{self, ConfigMeta},
+    resolve_config_dir, resolve_live_config, store_files, warning,
+};
+
+/// A stored config along with what `list` knows about it.
+pub struct ListEntry {
+    pub name: String,
+    pub active: bool,
+    pub meta: ConfigMeta,
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+}
+
+/// A config as printed by `list --json`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonEntry<'a> {
+    name: &'a str,
+    active: bool,
+    #[serde(flatten)]
+    meta: &'a ConfigMeta,
+    /// With `--with-status`: whether the live config is this config's file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    linked: Option<bool>,
+    /// With `--with-status`: whether the live config has this config's
+    /// content, linked or not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_sync: Option<bool>,
+}
+
+/// A field `list --columns` can display.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Name,
+    Active,
+    Description,
+    Modified,
+    Size,
+    Tags,
+}
+
+impl Column {
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "NAME",
+            Column::Active => "ACTIVE",
+            Column::Description => "DESCRIPTION",
+            Column::Modified => "MODIFIED",
+            Column::Size => "SIZE",
+            Column::Tags => "TAGS",
+        }
+    }
+
+    /// Whether the column needs each file's metadata, which costs a `stat`
+    /// per config.
+    fn needs_metadata(self) -> bool {
+        matches!(self, Column::Modified | Column::Size)
+    }
+
+    fn value(self, entry: &ListEntry) -> String {
+        match self {
+            Column::Name => entry.name.clone(),
+            Column::Active => if entry.active { "*" } else { "" }.to_string(),
+            Column::Description => entry
+                .meta
+                .description
+                .as_deref()
+                .unwrap_or_default()
+                .replace('\n', " "),
+            Column::Modified => entry
+                .modified
+                .map(|modified| humantime::format_rfc3339_seconds(modified).to_string())
+                .unwrap_or_default(),
+            Column::Size => entry.size.to_string(),
+            Column::Tags => entry
+                .meta
+                .tags
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// A piece of a `list --output-template`.
+#[derive(Clone, Debug)]
+enum Piece {
+    Text(String),
+    Field(Column),
+}
+
+/// A `list --output-template` format string, such as `{name}\t{mtime}`,
+/// expanded once per config. `{{` and `}}` stand for literal braces.
+#[derive(Clone, Debug)]
+pub struct OutputTemplate(Vec<Piece>);
+
+impl OutputTemplate {
+    /// The placeholders a template may use, and the column each expands to.
+    const PLACEHOLDERS: [(&'static str, Column); 6] = [
+        ("name", Column::Name),
+        ("current", Column::Active),
+        ("mtime", Column::Modified),
+        ("desc", Column::Description),
+        ("size", Column::Size),
+        ("tags", Column::Tags),
+    ];
+
+    /// Parse `template`, rejecting unknown placeholders and unmatched braces
+    /// before anything is listed.
+    pub fn parse(template: &str) -> Result<OutputTemplate, String> {
+        let mut pieces = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err("a `{` is never closed, write `{{` for a brace".to_string());
+                    };
+                    let placeholder = &rest[..end];
+                    let Some((_, column)) = Self::PLACEHOLDERS
+                        .iter()
+                        .find(|(name, _)| *name == placeholder)
+                    else {
+                        let known = Self::PLACEHOLDERS.map(|(name, _)| format!("{{{name}}}"));
+                        return Err(format!(
+                            "unknown placeholder {{{placeholder}}}, expected one of {}",
+                            known.join(", ")
+                        ));
+                    };
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    pieces.push(Piece::Field(*column));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err("a `}` is never opened, write `}}` for a brace".to_string()),
+                c => text.push(c),
+            }
+        }
+        pieces.push(Piece::Text(text));
+        Ok(OutputTemplate(pieces))
+    }
+
+    fn needs_metadata(&self) -> bool {
+        self.0
+            .iter()
+            .any(|piece| matches!(piece, Piece::Field(column) if column.needs_metadata()))
+    }
+
+    fn expand(&self, entry: &ListEntry) -> String {
+        self.0
+            .iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => text.clone(),
+                Piece::Field(column) => column.value(entry),
+            })
+            .collect()
+    }
+}
+
+/// Print one line per config, `template` expanded with its fields, leaving
+/// out disabled ones unless `all` is set. Only configs with every one of
+/// `tags` are printed.
+pub fn list_templated(template: &OutputTemplate, tags: &[String], all: bool) -> io::Result<()> {
+    let entries = filtered(template.needs_metadata(), tags, all)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    for entry in &entries {
+        writeln!(out, "{}", template.expand(entry))?;
+    }
+    out.flush()
+}
+
+/// Every readable config in the store, grouped ones included, sorted by
+/// name, from a single walk of the store and a single metadata load. File
+/// metadata is only fetched `with_metadata`, otherwise `modified` and `size`
+/// are left empty. Entries that cannot be read are reported and skipped.
+pub fn entries(with_metadata: bool) -> io::Result<Vec<ListEntry>> {
+    let path = resolve_config_dir()?;
+    let current = current_config()?.unwrap_or_default();
+    let mut metas = meta::load()?;
+
+    let mut entries = vec![];
+    for (file_name, entry) in store_files(&path)? {
+        let Some(name) = file_name.strip_suffix(".toml") else {
+            continue;
+        };
+
+        let (modified, size) = if with_metadata {
+            match entry.metadata() {
+                Ok(metadata) => (metadata.modified().ok(), metadata.len()),
+                Err(err) => {
+                    // stderr, like store_files, to keep machine output clean.
+                    warning::eprint(format!("Skipped {name}, it could not be read: {err}"));
+                    continue;
+                }
+            }
+        } else {
+            (None, 0)
+        };
+
+        entries.push(ListEntry {
+            name: name.to_string(),
+            active: name == current,
+            meta: metas.remove(name).unwrap_or_default(),
+            modified,
+            size,
+        });
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+impl ListEntry {
+    /// The name as shown in the plain list, with its state.
+    fn label(&self) -> String {
+        let states = [
+            (self.active, "current"),
+            (self.meta.disabled, "disabled"),
+            (self.meta.fragment, "fragment"),
+        ]
+        .into_iter()
+        .filter_map(|(set, state)| set.then_some(state))
+        .collect::<Vec<_>>();
+
+        if states.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, states.join(", "))
+        }
+    }
+}
+
+/// The [`entries`] with every one of `tags`, leaving out disabled ones unless
+/// `all` is set.
+fn filtered(with_metadata: bool, tags: &[String], all: bool) -> io::Result<Vec<ListEntry>> {
+    let mut entries = entries(with_metadata)?;
+    if !all {
+        entries.retain(|entry| !entry.meta.disabled);
+    }
+    entries.retain(|entry| tags.iter().all(|tag| entry.meta.tags.contains(tag)));
+    Ok(entries)
+}
+
+/// Print the stored configs, leaving out disabled ones unless `all` is set.
+/// Only configs with every one of `tags` are printed. With `tree`, configs
+/// are nested under the config they extend. With `stale`, only configs
+/// [`links::stale_reason`] flags are printed, with the reason. With
+/// `modified_since_switch`, only inactive configs modified after they were
+/// last switched away from are printed. With `json`,
+/// the configs are printed as a JSON array, including their link status if
+/// it is `Some(true)`.
+pub fn list_config(
+    columns: Option<&[Column]>,
+    tags: &[String],
+    all: bool,
+    tree: bool,
+    stale: bool,
+    modified_since_switch: bool,
+    json: Option<bool>,
+) -> io::Result<()> {
+    let with_metadata = modified_since_switch
+        || columns.is_some_and(|columns| columns.iter().any(|c| c.needs_metadata()));
+    let entries = filtered(with_metadata, tags, all)?;
+
+    if tree {
+        return print_tree(&entries);
+    }
+    if let Some(with_status) = json {
+        return print_json(&entries, with_status);
+    }
+    if stale {
+        println!("List of stale profiles:");
+        for entry in &entries {
+            if let Some(reason) = links::stale_reason(entry)? {
+                println!("- {}: {reason}", entry.label());
+            }
+        }
+        return Ok(());
+    }
+    if modified_since_switch {
+        return print_modified_since_switch(&entries);
+    }
+
+    // Buffered, since stdout flushes every line and a large store has
+    // hundreds of them.
+    let mut out = BufWriter::new(io::stdout().lock());
+    let Some(columns) = columns else {
+        writeln!(out, "List of profiles:")?;
+        for entry in &entries {
+            writeln!(out, "- {}", entry.label())?;
+        }
+        // As after `switch --none`.
+        if current_config()?.is_none() && fs::symlink_metadata(resolve_live_config()?).is_err() {
+            writeln!(out, "No config is active, cargo uses its defaults")?;
+        }
+        return out.flush();
+    };
+
+    let rows = entries
+        .iter()
+        .map(|entry| columns.iter().map(|column| column.value(entry)).collect())
+        .collect::<Vec<Vec<_>>>();
+
+    let widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.header().len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let headers = columns.iter().map(|column| column.header().to_string());
+    print_row(&mut out, &headers.collect::<Vec<_>>(), &widths)?;
+    for row in &rows {
+        print_row(&mut out, row, &widths)?;
+    }
+    out.flush()
+}
+
+/// Print the inactive `entries` whose file changed after they were last
+/// switched away from. The active config is left out, since editing the
+/// live config edits it, and so are configs that were never active.
+fn print_modified_since_switch(entries: &[ListEntry]) -> io::Result<()> {
+    let last_active = history::last_active()?;
+    println!("List of profiles modified since they were last active:");
+    for entry in entries.iter().filter(|entry| !entry.active) {
+        let (Some(modified), Some(left)) = (entry.modified, last_active.get(&entry.name)) else {
+            continue;
+        };
+        if modified > *left {
+            println!(
+                "- {}: modified {}, last active {}",
+                entry.label(),
+                humantime::format_rfc3339_seconds(modified),
+                humantime::format_rfc3339_seconds(*left)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print `entries` as JSON. `with_status` compares every config against the
+/// live config, which costs a `stat` each and a read for those not linked.
+fn print_json(entries: &[ListEntry], with_status: bool) -> io::Result<()> {
+    let live = resolve_live_config()?;
+    let mut json = vec![];
+    for entry in entries {
+        let (linked, in_sync) = if with_status {
+            let mut stored = resolve_config_dir()?;
+            stored.push(format!("{}.toml", entry.name));
+            let state = links::live_state(&entry.name, &stored, &live)?;
+            (
+                Some(matches!(state, LiveState::Linked)),
+                Some(matches!(
+                    state,
+                    LiveState::Linked | LiveState::Resolved | LiveState::Copy
+                )),
+            )
+        } else {
+            (None, None)
+        };
+        json.push(JsonEntry {
+            name: &entry.name,
+            active: entry.active,
+            meta: &entry.meta,
+            linked,
+            in_sync,
+        });
+    }
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json).map_err(io::Error::other)?
+    );
+    Ok(())
+}
+
+/// Print every tag in use with the number of configs carrying it, disabled
+/// configs included.
+pub fn list_tags() -> io::Result<()> {
+    let mut counts = BTreeMap::<String, usize>::new();
+    // Read through the store rather than the sidecar, which keeps the
+    // metadata of trashed configs for `trash restore`.
+    for entry in entries(false)? {
+        for tag in entry.meta.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    println!("List of tags:");
+    for (tag, count) in counts {
+        println!("- {tag} ({count})");
+    }
+    Ok(())
+}
+
+fn print_row(out: &mut impl Write, cells: &[String], widths: &[usize]) -> io::Result<()> {
+    let line = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:width$}"))
+        .collect::<Vec<_>>()
+        .join("  ");
+    writeln!(out, "{}", line.trim_end())
+}
+
+/// Print configs nested under the config their `extends` key names. Configs
+/// extending nothing, a missing config or a cycle sit at the root; configs in
+/// a cycle are reported instead of shown.
+fn print_tree(entries: &[ListEntry]) -> io::Result<()> {
+    let names = entries
+        .iter()
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
+    let bases = inherit::bases(&names)?;
+
+    let mut in_cycle = BTreeSet::new();
+    for name in &names {
+        let Some(cycle) = inherit::find_cycle(name, &bases) else {
+            continue;
+        };
+        if in_cycle.insert(cycle[0].clone()) {
+            warning::print(format!("Inheritance cycle {}", cycle.join(" -> ")));
+            in_cycle.extend(cycle);
+        }
+    }
+
+    let mut roots = vec![];
+    let mut children = BTreeMap::<&str, Vec<&ListEntry>>::new();
+    for entry in entries {
+        if in_cycle.contains(&entry.name) {
+            continue;
+        }
+        match bases.get(&entry.name) {
+            Some(base) if names.contains(base) && !in_cycle.contains(base) => {
+                children.entry(base.as_str()).or_default().push(entry)
+            }
+            _ => roots.push(entry),
+        }
+    }
+
+    println!("List of profiles:");
+    for root in roots {
+        print_branch(root, &children, 0);
+    }
+    Ok(())
+}
+
+fn print_branch(entry: &ListEntry, children: &BTreeMap<&str, Vec<&ListEntry>>, depth: usize) {
+    println!("{}- {}", "  ".repeat(depth), entry.label());
+    for child in children.get(entry.name.as_str()).into_iter().flatten() {
+        print_branch(child, children, depth + 1);
+    }
+}
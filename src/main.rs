@@ -1,181 +1,2853 @@
 use miette::IntoDiagnostic;
 use owo_colors::OwoColorize;
 use std::{
+    env,
     fs::{self, hard_link, remove_file, File},
-    io::{self, Read, Write},
-    path::PathBuf,
-    process::Command,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::{self, Command, ExitStatus},
+    sync::{Mutex, Once, OnceLock},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use diff::DiffFormat;
+use format::Format;
+use hooks::Hook;
+use regex::Regex;
+use tracing_subscriber::{
+    filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+};
+
+mod alias;
+mod batch;
+mod checksum;
+mod clipboard;
+mod compat;
+mod completions;
+mod diff;
+mod doctor;
+mod format;
+mod fragments;
+mod get;
+mod history;
+mod hooks;
+mod inherit;
+mod links;
+mod lint;
+mod list;
+mod merge;
+mod meta;
+mod migrate;
+mod notify;
+mod parallel;
+mod paranoid;
+mod project;
+mod report;
+mod run;
+mod schema;
+mod self_check;
+mod settings;
+mod snapshot;
+mod state;
+mod stats;
+mod template;
+#[cfg(test)]
+mod test_support;
+mod trace;
+mod transfer;
+mod trash;
+mod verify;
+mod warning;
+mod watch;
+mod wizard;
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Switch cargo configurations with ease.")]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: Config,
+}
+
+/// Options shared by every command.
+#[derive(Args, Debug, Default)]
+struct GlobalArgs {
+    /// Manage the cargo home at this path instead of ~/.cargo, such as a
+    /// system-wide one shared by CI jobs or build users
+    #[arg(long, global = true, value_name = "DIR")]
+    target_cargo_home: Option<PathBuf>,
+    /// Never create the config directory, erroring if it is missing
+    #[arg(long, global = true)]
+    no_create_dir: bool,
+    /// File to link the active config to, instead of config.toml in the
+    /// cargo dir. Relative paths are relative to the cargo dir
+    #[arg(long, global = true, env = "CARGO_CONFIG_TARGET", value_name = "PATH")]
+    config_target: Option<PathBuf>,
+    /// Reject new config names that do not match the name-pattern setting
+    #[arg(long, global = true)]
+    strict_names: bool,
+    /// Allow new config names that clash with the tool's own files, such as
+    /// `config` or `hooks`, or with an existing config by case alone
+    #[arg(long, global = true)]
+    allow_reserved: bool,
+    /// Warn when the live config was changed by something other than this
+    /// tool since the last run
+    #[arg(long, global = true)]
+    watch_cargo_home: bool,
+    /// Refuse to create or import a config identical to an existing one,
+    /// instead of only warning
+    #[arg(long, global = true)]
+    dedupe_on_create: bool,
+    /// Report what startup initialisation did, even if it did nothing
+    #[arg(long, global = true)]
+    print_init: bool,
+    /// Permissions for the config directory when it is created, in octal
+    /// (Unix only)
+    #[arg(long, global = true, value_name = "MODE", value_parser = parse_mode)]
+    dir_mode: Option<u32>,
+    /// Permissions for newly stored configs, in octal, since they may hold
+    /// tokens (Unix only)
+    #[arg(long, global = true, value_name = "MODE", value_parser = parse_mode)]
+    file_mode: Option<u32>,
+    /// Exit with an error if any warning was printed, such as drift or a
+    /// duplicate config, for CI
+    #[arg(long, global = true)]
+    exit_on_warning: bool,
+    /// Carry on past failures in commands over several configs, such as
+    /// create, remove and migrate-format --all, and report them all at the end
+    #[arg(short, long, global = true)]
+    keep_going: bool,
+    /// Ask before anything that deletes or replaces a config, such as
+    /// switch, remove or restore --force
+    #[arg(long, global = true)]
+    confirm_destructive: bool,
+    /// Answer yes to every confirmation, such as --confirm-destructive
+    /// prompts or replacing a config with rename --force, for scripts
+    #[arg(short, long, global = true, visible_alias = "assume-yes")]
+    yes: bool,
+    /// Ask on stdin even when it is not a terminal, such as to pipe the
+    /// answers in, instead of refusing or assuming no
+    #[arg(long, global = true, conflicts_with = "yes")]
+    force_interactive: bool,
+    /// Print the resolved cargo dir, config dir and live config to stderr
+    /// before running the command
+    #[arg(long, global = true)]
+    print_dir: bool,
+    /// Resolve project paths, such as `link --into`, `switch --relative-to`
+    /// and the project configs `get --effective` reads, from DIR instead of
+    /// the working directory. The cargo home is unaffected
+    #[arg(long, global = true, value_name = "DIR", value_parser = parse_dir)]
+    cwd: Option<PathBuf>,
+    /// Algorithm for the checksums the tool takes, such as duplicate
+    /// detection, the --watch-cargo-home record and `integrity`
+    #[arg(long, global = true, value_enum, default_value_t)]
+    checksum_algo: checksum::Algo,
+    /// Check that create leaves the active config and the live config.toml
+    /// exactly as they were, and fail if it did not
+    #[arg(long, global = true, visible_alias = "preserve-current-on-create")]
+    paranoid: bool,
+    /// Write a diagnostic bundle for a bug report to PATH once the command
+    /// has run: the command, paths, doctor checks and which configs parse,
+    /// without any config contents
+    #[arg(long, global = true, value_name = "PATH")]
+    report_file: Option<PathBuf>,
+    /// Log every operation to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Write the operation log to a file, regardless of --verbose
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    /// Append to the log file instead of truncating it
+    #[arg(long, global = true, requires = "log_file")]
+    append_log: bool,
+    /// Set for commands that only read state, which never create the config
+    /// directory either
+    #[arg(skip)]
+    read_only: bool,
+}
+
+static GLOBAL: OnceLock<GlobalArgs> = OnceLock::new();
+
+fn global() -> &'static GlobalArgs {
+    GLOBAL.get_or_init(GlobalArgs::default)
+}
+
+#[derive(Subcommand, Debug)]
+enum Config {
+    /// Create a new cargo config
+    Create(CreateArgs),
+    /// Switch between cargo configs
+    Switch(SwitchArgs),
+    /// Toggle back to the previous config, so running it twice returns to
+    /// where you started
+    Undo,
+    /// List configs
+    List(ListArgs),
+    /// Remove a config
+    Remove {
+        /// Move the config to the trash so it can be restored later
+        #[arg(long, overrides_with = "no_trash")]
+        trash: bool,
+        /// Delete the config permanently, even if trash-on-remove is set
+        #[arg(long)]
+        no_trash: bool,
+        /// Print a JSON summary of each config's outcome
+        #[arg(long)]
+        json: bool,
+        /// Skip configs that do not exist instead of failing, for cleanup
+        /// scripts that may run twice
+        #[arg(long)]
+        ignore_missing: bool,
+        #[arg(value_parser = parse_name, required = true)]
+        values: Vec<String>,
+    },
+    /// Launch an editor to edit a config
+    Edit(EditArgs),
+    /// Show the differences between two configs
+    Diff {
+        /// Show what switching to A would change in the live config
+        #[arg(long, conflicts_with = "b")]
+        against_cargo: bool,
+        /// Print the changed lines as JSON records instead of a diff, the same
+        /// as --format json
+        #[arg(long, visible_alias = "porcelain", conflicts_with = "format")]
+        json: bool,
+        /// Print a colored diff, JSON records, or a patch for `patch` and
+        /// `git apply`
+        #[arg(long, value_enum, default_value_t)]
+        format: DiffFormat,
+        /// Compare A and B key by key against their common ancestor,
+        /// showing what each changed and flagging conflicting changes
+        #[arg(
+            long,
+            value_name = "BASE",
+            visible_alias = "three-way",
+            requires = "b",
+            conflicts_with = "against_cargo"
+        )]
+        base: Option<String>,
+        a: String,
+        #[arg(required_unless_present = "against_cargo")]
+        b: Option<String>,
+    },
+    /// Check that a config is valid, defaulting to the active one
+    Verify {
+        /// Compare the config against the values cargo actually resolves
+        #[arg(long, conflicts_with = "all")]
+        against_cargo: bool,
+        /// Verify every config
+        #[arg(long, conflicts_with = "value")]
+        all: bool,
+        /// Configs to check at once with --all, defaulting to the CPU count
+        #[arg(short, long, requires = "all")]
+        jobs: Option<usize>,
+        value: Option<String>,
+    },
+    /// Link a config into a project, such as a crate's .cargo/config.toml,
+    /// without changing the active config
+    Link {
+        /// Path to link the config to, creating its parent directories
+        #[arg(long, value_name = "PATH")]
+        into: PathBuf,
+        /// Use a symlink instead of a hard link, e.g. across filesystems
+        #[arg(long)]
+        symlink: bool,
+        #[arg(value_parser = parse_name)]
+        value: String,
+    },
+    /// List the project links made with `link`
+    Links,
+    /// Check for common problems, such as a live config an editor rewrote as
+    /// a separate file
+    Doctor {
+        /// Offer to repair the problems found
+        #[arg(long, conflicts_with = "json")]
+        fix: bool,
+        /// Print each check's name, status and detail as JSON, for
+        /// monitoring
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that the tool's bookkeeping agrees with the store: the active
+    /// and previous configs exist, and no metadata or project link is kept
+    /// for a config that is gone
+    SelfCheck {
+        /// Prune the records that do not agree
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Check that the live config is still linked to the active config
+    VerifyLinks {
+        /// Relink the active config if its link is broken
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Relink the live config to the active config, such as after it was
+    /// deleted
+    #[command(visible_alias = "repair")]
+    RepairLink {
+        /// Also replace a live config that has different contents
+        #[arg(long, visible_alias = "force-relink")]
+        force: bool,
+        /// Also relink every project link, for after the cargo home was
+        /// moved or copied
+        #[arg(long)]
+        all: bool,
+    },
+    /// Warn about keys cargo does not recognise, defaulting to the active
+    /// config
+    Lint {
+        /// Lint every config
+        #[arg(long, conflicts_with = "value")]
+        all: bool,
+        /// Configs to check at once with --all, defaulting to the CPU count
+        #[arg(short, long, requires = "all")]
+        jobs: Option<usize>,
+        value: Option<String>,
+    },
+    /// Move a legacy extensionless ~/.cargo/config into the store and switch
+    /// to it
+    Migrate {
+        /// Name to store the config under
+        #[arg(long, default_value = "legacy", value_parser = parse_name)]
+        name: String,
+    },
+    /// Rewrite deprecated cargo config keys, keeping a backup of the original
+    MigrateFormat {
+        /// Migrate every config
+        #[arg(long, conflicts_with = "value")]
+        all: bool,
+        value: Option<String>,
+    },
+    /// Rename a config, keeping it active if it was
+    Rename {
+        /// Replace an existing config with the new name, after confirming
+        #[arg(long)]
+        force: bool,
+        #[arg(value_parser = parse_name)]
+        from: String,
+        #[arg(value_parser = parse_name)]
+        to: String,
+    },
+    /// Copy a config to a new name
+    Copy {
+        #[arg(value_parser = parse_name)]
+        from: String,
+        #[arg(value_parser = parse_name)]
+        to: String,
+    },
+    /// Print a config
+    Show {
+        /// Match the name ignoring case, if only one config matches
+        #[arg(short = 'i', long)]
+        case_insensitive: bool,
+        /// Print the config converted to this format
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+        /// Mark the lines that differ from the live config
+        #[arg(long, conflicts_with = "format")]
+        compare_current: bool,
+        /// Print what a switch would write as the live config, with its
+        /// `extends` chain and includes merged and a template rendered
+        #[arg(long, conflicts_with = "compare_current")]
+        resolved: bool,
+        /// Copy the config to the system clipboard instead of printing it
+        #[arg(
+            long,
+            visible_alias = "into-clipboard",
+            conflicts_with = "compare_current"
+        )]
+        clipboard: bool,
+        #[arg(value_parser = parse_name)]
+        value: String,
+    },
+    /// Print the checksum of each config, or of the named ones, for
+    /// verifying them with an external tool
+    Integrity {
+        #[arg(value_parser = parse_name)]
+        values: Vec<String>,
+    },
+    /// Explain where cargo reads config from here, highest precedence first,
+    /// and what each source sets
+    Trace,
+    /// Print the value of a dotted key, such as `build.target`, in the
+    /// active config
+    Get {
+        /// Print what cargo resolves instead, with project configs and
+        /// CARGO_* environment variables applied
+        #[arg(long)]
+        effective: bool,
+        key: String,
+    },
+    /// Run cargo with a config without switching to it
+    Run {
+        #[arg(value_parser = parse_name)]
+        value: String,
+        /// Arguments to pass to cargo
+        #[arg(last = true, required = true)]
+        args: Vec<String>,
+    },
+    /// Build a config from the numbered fragments in its NAME.d directory
+    Assemble {
+        #[arg(value_parser = parse_name)]
+        value: String,
+    },
+    /// Show or set a config's description
+    Describe {
+        value: String,
+        description: Option<String>,
+    },
+    /// Summarise the store: how many configs, their sizes, the newest and
+    /// the active one
+    Stats {
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Print JSON Schemas for the tool's state files and JSON output
+    #[command(hide = true)]
+    JsonSchema {
+        #[arg(value_enum)]
+        document: Option<schema::Document>,
+    },
+    /// Print the active config for a shell prompt, or nothing if none is
+    /// active
+    Prompt {
+        /// Template for the output, `{name}` is replaced with the config name
+        #[arg(long, default_value = "{name}")]
+        format: String,
+    },
+    /// Hide a config from list and completion without removing it
+    Disable { value: String },
+    /// Show a disabled config again
+    Enable { value: String },
+    /// Mark a config as a fragment, which other configs `include` and which
+    /// cannot be switched to
+    Fragment {
+        /// Make it a full config again
+        #[arg(long)]
+        unset: bool,
+        value: String,
+    },
+    /// Mark a config as a template, whose `${hostname}`, `${arch}`, `${os}`
+    /// and `${env.NAME}` placeholders are filled in on switch
+    Template {
+        /// Make it a plain config again
+        #[arg(long)]
+        unset: bool,
+        value: String,
+    },
+    /// Print a config, or write it to a file
+    Export(ExportArgs),
+    /// Store a config file, restoring metadata exported with it
+    Import {
+        /// Name to store the config under, defaulting to the file name
+        #[arg(long, value_parser = parse_name)]
+        name: Option<String>,
+        /// Format of the file, guessed from its extension by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Copy the file a symlink points to (the default)
+        #[arg(long, overrides_with = "no_follow_symlinks")]
+        follow_symlinks: bool,
+        /// Refuse to import a symlink
+        #[arg(long)]
+        no_follow_symlinks: bool,
+        /// Skip the file if an existing config has the same content
+        #[arg(long)]
+        skip_duplicates: bool,
+        path: PathBuf,
+    },
+    /// Print everything the tool tracks: the active config, configs and
+    /// their metadata, and settings
+    Dump {
+        /// Emit JSON instead of TOML
+        #[arg(long)]
+        json: bool,
+        /// Include the contents of every config
+        #[arg(long)]
+        bundle: bool,
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Clear the tool's bookkeeping (active and previous config records, the
+    /// live config hash), keeping every config and its metadata
+    Reset {
+        /// Record the stored config the live config.toml is linked to (or a
+        /// copy of) as active again
+        #[arg(long)]
+        infer: bool,
+    },
+    /// Recreate the state described by a dump
+    Restore {
+        /// Overwrite existing configs with bundled ones
+        #[arg(short, long)]
+        force: bool,
+        path: PathBuf,
+    },
+    /// Manage the cargo `[alias]` table of a config
+    AliasCargo {
+        /// Config to edit, defaulting to the active one
+        #[arg(long, global = true, value_name = "NAME", value_parser = parse_name)]
+        config: Option<String>,
+        #[command(subcommand)]
+        command: AliasCommand,
+    },
+    /// Add or remove a config's tags
+    Tag {
+        #[command(subcommand)]
+        command: TagCommand,
+    },
+    /// List every tag in use and how many configs have it
+    Tags,
+    /// Manage snapshots of the live config taken by `switch --snapshot-before`
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Manage removed configs
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommand,
+    },
+    /// Print config names one per line, for shell completion
+    #[command(hide = true)]
+    Names,
+}
+
+#[derive(Args, Debug)]
+struct CreateArgs {
+    /// Fill the new config from a template read on stdin
+    #[arg(long)]
+    template_stdin: bool,
+    /// Value for a `${KEY}` placeholder in the template
+    #[arg(long, value_name = "KEY=VALUE", value_parser = template::parse_var, requires = "template_stdin")]
+    var: Vec<(String, String)>,
+    /// Leave placeholders without a --var in place instead of erroring
+    #[arg(long, requires = "template_stdin")]
+    allow_unset: bool,
+    /// Build the config by answering questions about common settings, such
+    /// as an alternate registry, build jobs and offline mode
+    #[arg(long, conflicts_with_all = ["template_stdin", "json"])]
+    interactive: bool,
+    /// Print a JSON summary of each config's outcome
+    #[arg(long)]
+    json: bool,
+    /// Switch to the new config once it is created
+    #[arg(long, visible_alias = "activate", conflicts_with = "json")]
+    link: bool,
+    /// Mark the new configs as templates, rendered with this machine's
+    /// values on switch
+    #[arg(long)]
+    as_template: bool,
+    /// Create the groups in a namespaced name such as `work/ci` if they do
+    /// not exist yet
+    #[arg(short, long)]
+    parents: bool,
+    /// Configs to create, each from the same template if one is given
+    #[arg(value_parser = parse_name, required = true)]
+    values: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct SwitchArgs {
+    /// Remove a directory in the way of config.toml after confirmation, and
+    /// allow switching to a disabled config
+    #[arg(short, long)]
+    force: bool,
+    /// Match the name ignoring case, if only one config matches
+    #[arg(short = 'i', long)]
+    case_insensitive: bool,
+    /// Only switch while running a command (or $SHELL), then switch back
+    #[arg(long)]
+    temporary: bool,
+    /// Only check whether the config is already active and linked, exiting
+    /// non-zero if a switch is needed
+    #[arg(long, conflicts_with_all = ["temporary", "previous", "command"])]
+    check_only: bool,
+    /// Step back to the recorded previous config. Unlike `undo`, this does
+    /// not toggle: the previous config is cleared, not swapped
+    #[arg(long, conflicts_with = "value")]
+    previous: bool,
+    /// Do nothing and print nothing if the config is already active and
+    /// linked, for provisioning that switches unconditionally
+    #[arg(
+        long,
+        visible_alias = "quiet-if-unchanged",
+        conflicts_with_all = ["temporary", "check_only", "relative_to", "no_current_update"]
+    )]
+    quiet_if_same: bool,
+    /// Remove the live config so cargo uses its defaults, recording that no
+    /// config is active. Switch to a config by name to undo it
+    #[arg(
+        long,
+        conflicts_with_all = ["value", "previous", "temporary", "check_only", "relative_to", "no_current_update", "snapshot_before"]
+    )]
+    none: bool,
+    #[arg(value_parser = parse_name, required_unless_present_any = ["previous", "none"])]
+    value: Option<String>,
+    /// Link the config without recording it as the active one, for
+    /// diagnosing or scripting drift between the link and the bookkeeping
+    #[arg(long, conflicts_with = "temporary")]
+    no_current_update: bool,
+    /// Link the config with a symlink instead of a hard link
+    #[arg(long)]
+    symlink: bool,
+    /// Make the symlink relative, so the cargo home can be moved or synced
+    #[arg(long, requires = "symlink")]
+    relative: bool,
+    /// Link into DIR/config.toml instead of the cargo home, as a one-off that
+    /// leaves the recorded active config alone
+    #[arg(long, value_name = "DIR", conflicts_with = "temporary")]
+    relative_to: Option<PathBuf>,
+    /// Copy the live config into a snapshot before switching, labelled
+    /// LABEL or the current time
+    #[arg(
+        long,
+        value_name = "LABEL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        conflicts_with_all = ["temporary", "check_only"]
+    )]
+    snapshot_before: Option<String>,
+    /// Keep the new config if the post-switch hook fails, instead of
+    /// switching back to the previous one
+    #[arg(long)]
+    keep_on_hook_failure: bool,
+    /// Append the new active config's name to PATH, a file or FIFO, once
+    /// the switch succeeds, for integrations that react to switches. An empty
+    /// line means no config, after --none
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["temporary", "check_only", "no_current_update", "relative_to"])]
+    notify: Option<PathBuf>,
+    /// Command to run with --temporary
+    #[arg(last = true, requires = "temporary")]
+    command: Vec<String>,
+}
+
+impl SwitchArgs {
+    /// The config to switch to: the given name, or the previous config with
+    /// `--previous`.
+    fn target(&self) -> io::Result<String> {
+        match &self.value {
+            Some(value) => resolve_name(value, self.case_insensitive),
+            None => history::require_previous(),
+        }
+    }
+
+    fn options(&self) -> SwitchOptions {
+        SwitchOptions {
+            force: self.force,
+            keep_current: self.no_current_update || self.relative_to.is_some(),
+            keep_previous: false,
+            live: self.relative_to.as_ref().map(|dir| match &global().cwd {
+                Some(cwd) => cwd.join(dir).join("config.toml"),
+                None => dir.join("config.toml"),
+            }),
+            link: match (self.symlink, self.relative) {
+                (false, _) => LinkMode::Hard,
+                (true, false) => LinkMode::Symlink,
+                (true, true) => LinkMode::RelativeSymlink,
+            },
+            keep_on_hook_failure: self.keep_on_hook_failure,
+            skip_hooks: false,
+        }
+    }
+}
+
+/// How a switch puts the config in place.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum LinkMode {
+    #[default]
+    Hard,
+    Symlink,
+    RelativeSymlink,
+}
+
+/// Everything a switch can be asked to do differently. The default is a
+/// plain switch.
+#[derive(Clone, Debug, Default)]
+struct SwitchOptions {
+    /// Remove a directory in the way of the live config after confirmation.
+    force: bool,
+    /// Leave `cargo-config-current` as it is, for diagnostics.
+    keep_current: bool,
+    /// Leave `cargo-config-previous` as it is, for switches that are undone
+    /// automatically.
+    keep_previous: bool,
+    /// Where to link the config instead of the resolved live config.
+    live: Option<PathBuf>,
+    link: LinkMode,
+    /// Keep the new config when the post-switch hook fails, instead of
+    /// switching back.
+    keep_on_hook_failure: bool,
+    /// Run no hooks, for rolling a switch back.
+    skip_hooks: bool,
+}
+
+#[derive(Args, Debug)]
+struct ListArgs {
+    /// Include disabled configs
+    #[arg(long)]
+    all: bool,
+    /// Nest configs under the config their `extends` key names
+    #[arg(long, conflicts_with = "columns")]
+    tree: bool,
+    /// Show a table of these fields, in order
+    #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+    columns: Option<Vec<list::Column>>,
+    /// Only list configs with this tag; repeat to require several
+    #[arg(long, value_name = "TAG")]
+    tag: Vec<String>,
+    /// Only list configs that look stale: copies of the live config, or
+    /// configs whose link is broken or left behind
+    #[arg(long, conflicts_with_all = ["tree", "columns"])]
+    stale: bool,
+    /// Only list configs whose file changed after they were last switched
+    /// away from, such as ones edited but never activated again
+    #[arg(long, conflicts_with_all = ["tree", "columns", "stale"])]
+    modified_since_switch: bool,
+    /// Print the configs and their metadata as JSON
+    #[arg(
+        long,
+        conflicts_with_all = ["tree", "columns", "stale", "modified_since_switch"]
+    )]
+    json: bool,
+    /// Add whether each config is linked to and in sync with the live
+    /// config to the JSON, which reads every config
+    #[arg(long, requires = "json")]
+    with_status: bool,
+    /// Print a line per config from this template, such as
+    /// `{name}\t{mtime}`. Placeholders are {name}, {current}, {mtime},
+    /// {desc}, {size} and {tags}
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        value_parser = list::OutputTemplate::parse,
+        conflicts_with_all = ["tree", "columns", "stale", "modified_since_switch", "json"]
+    )]
+    output_template: Option<list::OutputTemplate>,
+}
+
+#[derive(Args, Debug)]
+struct ExportArgs {
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Copy the export to the system clipboard instead of printing it
+    #[arg(long, visible_alias = "into-clipboard", conflicts_with = "output")]
+    clipboard: bool,
+    /// Embed the config's metadata as a comment header
+    #[arg(long)]
+    include_sidecars: bool,
+    /// Record this machine, the config name and the time in the comment
+    /// header, which `import` keeps as the config's provenance
+    #[arg(long)]
+    annotate: bool,
+    /// Only export these top-level sections
+    #[arg(long, value_name = "SECTION")]
+    only: Vec<String>,
+    /// Leave these top-level sections out, e.g. `registries`
+    #[arg(long, value_name = "SECTION")]
+    except: Vec<String>,
+    /// Convert the config to this format
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+    value: String,
+}
+
+#[derive(Args, Debug)]
+struct EditArgs {
+    #[arg(short, long)]
+    editor: String,
+    /// Block until this file is modified and then left alone, for editors
+    /// that return immediately
+    #[arg(long, value_name = "PATH")]
+    wait_for: Option<PathBuf>,
+    /// How long the waited-on file must stay unchanged, in milliseconds
+    #[arg(long, default_value_t = 500, requires = "wait_for")]
+    settle_ms: u64,
+    /// Wait for the editor to exit and show what changed
+    #[arg(long)]
+    diff: bool,
+    /// Edit a scratch copy, then show the changes and ask whether to save
+    /// them back
+    #[arg(long)]
+    temp: bool,
+    /// Only warn about invalid TOML after editing, instead of offering to
+    /// reopen the editor
+    #[arg(long)]
+    no_recheck: bool,
+    /// Open several configs in tabs, for editors known to support it
+    #[arg(long, conflicts_with = "split")]
+    tabs: bool,
+    /// Open several configs side by side, for editors known to support it
+    #[arg(long)]
+    split: bool,
+    /// Configs to edit in one editor, picked from a list when omitted
+    #[arg(value_parser = parse_name)]
+    values: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum AliasCommand {
+    /// List the aliases
+    List,
+    /// Add or replace an alias, such as `set b "build --release"`
+    Set { name: String, expansion: String },
+    /// Remove an alias
+    Remove { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+enum TagCommand {
+    /// Tag a config, such as `add ci work mirror`
+    Add {
+        #[arg(value_parser = parse_name)]
+        value: String,
+        #[arg(value_parser = parse_tag, required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a config
+    Remove {
+        #[arg(value_parser = parse_name)]
+        value: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// List snapshots
+    List,
+    /// Store a snapshot as a config, so it can be switched to
+    Restore {
+        /// Name for the config, defaulting to the snapshot's label
+        #[arg(long, value_parser = parse_name)]
+        name: Option<String>,
+        label: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TrashCommand {
+    /// List trashed configs
+    List,
+    /// Restore the most recently trashed copy of a config
+    Restore { value: String },
+    /// Permanently delete everything in the trash
+    Empty,
+}
+
+impl Config {
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Config::List(_)
+                | Config::Verify { .. }
+                | Config::VerifyLinks { repair: false }
+                | Config::Doctor { fix: false, .. }
+                | Config::SelfCheck { fix: false }
+                | Config::Switch(SwitchArgs {
+                    check_only: true,
+                    ..
+                })
+                | Config::Links
+                | Config::Lint { .. }
+                | Config::Names
+                | Config::Prompt { .. }
+                | Config::JsonSchema { .. }
+                | Config::Completions { .. }
+                | Config::Show { .. }
+                | Config::Get { .. }
+                | Config::Trace
+                | Config::Integrity { .. }
+                | Config::Stats { .. }
+                | Config::Dump { .. }
+                | Config::Export(_)
+                | Config::Describe {
+                    description: None,
+                    ..
+                }
+                | Config::Tags
+                | Config::Snapshot {
+                    command: SnapshotCommand::List
+                }
+                | Config::Trash {
+                    command: TrashCommand::List
+                }
+                | Config::AliasCargo {
+                    command: AliasCommand::List,
+                    ..
+                }
+        )
+    }
+}
+
+fn main() -> miette::Result<()> {
+    let Cli {
+        mut global,
+        command: cfg,
+    } = Cli::parse();
+    global.read_only = cfg.is_read_only();
+    init_logging(&global).into_diagnostic()?;
+    let exit_on_warning = global.exit_on_warning;
+    let print_dir = global.print_dir;
+    let report_file = global.report_file.clone();
+    let may_write = !global.read_only && !global.no_create_dir;
+    let _ = GLOBAL.set(global);
+
+    tracing::debug!(command = ?cfg, "starting");
+    if print_dir {
+        print_dirs().into_diagnostic()?;
+    }
+    // Completion and prompt output must stay clean, so they never warn.
+    let quiet = matches!(
+        cfg,
+        Config::Names | Config::Prompt { .. } | Config::Completions { .. }
+    );
+    let watch = !quiet && watch::enabled();
+    if watch {
+        if let Err(err) = watch::check_live_config() {
+            tracing::debug!("could not check the live config: {err}");
+        }
+    }
+
+    let result = run(cfg).inspect_err(|err| tracing::error!("{err}"));
+    if let Some(path) = &report_file {
+        match report::write(path, result.as_ref().err()) {
+            Ok(()) => eprintln!("Wrote a diagnostic report to {}", path.display()),
+            Err(err) => warning::eprint(format!(
+                "Could not write a report to {}: {err}",
+                path.display()
+            )),
+        }
+    }
+    // Once a hash is recorded, keep it current even without the flag so
+    // switches made here never look like outside changes. Read-only
+    // commands change nothing to record, and must not write.
+    if may_write && (watch || (!quiet && watch::has_record())) {
+        if let Err(err) = watch::record_live_config() {
+            tracing::debug!("could not record the live config: {err}");
+        }
+    }
+    result?;
+
+    let warnings = warning::emitted();
+    if exit_on_warning && !warnings.is_empty() {
+        return Err(miette::miette!(
+            code = "exit-on-warning",
+            help = "rerun without --exit-on-warning to only warn",
+            "{} warning{} with --exit-on-warning:\n{}",
+            warnings.len(),
+            if warnings.len() == 1 { "" } else { "s" },
+            warnings.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+fn run(cfg: Config) -> miette::Result<()> {
+    // Prompts run constantly, and schemas and completion scripts need no
+    // store, so they skip the startup bookkeeping entirely.
+    match &cfg {
+        Config::Prompt { format } => return prompt(format).into_diagnostic(),
+        Config::JsonSchema { document } => {
+            return schema::print_schema(*document).into_diagnostic()
+        }
+        Config::Completions { shell } => {
+            return completions::print_completions(*shell).into_diagnostic()
+        }
+        _ => {}
+    }
+    let report = initialise().into_diagnostic()?;
+    if !report.is_empty() {
+        warning::print("Found an existing config in the Cargo directory and took it over");
+        report.print();
+    } else if global().print_init {
+        println!("Success:   {}  Nothing to initialise", "✓".green());
+    }
+
+    match cfg {
+        Config::Create(args) => {
+            let template = args
+                .template_stdin
+                .then(|| {
+                    let mut template = String::new();
+                    io::stdin().read_to_string(&mut template).map(|_| template)
+                })
+                .transpose()
+                .into_diagnostic()?;
+            let template = match &args.values[..] {
+                [name] if args.interactive => {
+                    // Checked before asking anything, so no answers are wasted.
+                    check_new_name(name).into_diagnostic()?;
+                    if config_path(name).into_diagnostic()?.exists() {
+                        miette::bail!("{name} already exists");
+                    }
+                    Some(wizard::build().into_diagnostic()?)
+                }
+                _ if args.interactive => {
+                    miette::bail!("--interactive takes a single config to create")
+                }
+                _ => template,
+            };
+            let link = match &args.values[..] {
+                [name] if args.link => Some(name),
+                _ if args.link => miette::bail!("--link takes a single config to create"),
+                _ => None,
+            };
+
+            let before = paranoid::capture().into_diagnostic()?;
+            batch::run_batch(
+                &args.values,
+                args.json,
+                |name| format!("Created {name}.toml"),
+                |name| create_config(&args, name, template.as_deref()),
+            )
+            .into_diagnostic()?;
+            paranoid::check_unchanged(before, "create").into_diagnostic()?;
+            if let Some(name) = link {
+                confirm_switch(name).into_diagnostic()?;
+                switch_config(name, &SwitchOptions::default())
+                    .map(|_| println!("Success:   {}  Switched to {name}", "✓".green()))
+                    .into_diagnostic()?;
+            }
+            Ok(())
+        }
+        Config::Switch(
+            ref args @ SwitchArgs {
+                temporary: true,
+                ref command,
+                ..
+            },
+        ) => {
+            let value = args.target().into_diagnostic()?;
+            check_enabled(&value, args.force).into_diagnostic()?;
+            confirm_switch(&value).into_diagnostic()?;
+            let status = temporary_switch(&value, &args.options(), command).into_diagnostic()?;
+            if !status.success() {
+                process::exit(status.code().unwrap_or(1));
+            }
+            Ok(())
+        }
+        Config::Switch(
+            ref args @ SwitchArgs {
+                check_only: true, ..
+            },
+        ) => {
+            let value = args.target().into_diagnostic()?;
+            links::check_active(&value).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Switch(ref args @ SwitchArgs { none: true, .. }) => {
+            if current_config().into_diagnostic()?.is_none()
+                && fs::symlink_metadata(resolve_live_config().into_diagnostic()?).is_err()
+            {
+                if !args.quiet_if_same {
+                    println!("No config is active, cargo already uses its defaults");
+                }
+                return Ok(());
+            }
+            confirm_destructive("remove the live config").into_diagnostic()?;
+            switch_to_none(args.keep_on_hook_failure)
+                .map(|_| {
+                    println!(
+                        "Success:   {}  Removed the live config, cargo now uses its defaults",
+                        "✓".green()
+                    )
+                })
+                .into_diagnostic()?;
+            if let Some(path) = &args.notify {
+                notify::notify(path, "");
+            }
+            Ok(())
+        }
+        Config::Switch(args) => {
+            let value = args.target().into_diagnostic()?;
+            if args.quiet_if_same && links::is_active(&value).into_diagnostic()? {
+                tracing::debug!(name = value, "already active, not switching");
+                return Ok(());
+            }
+            check_enabled(&value, args.force).into_diagnostic()?;
+            confirm_switch(&value).into_diagnostic()?;
+            if let Some(label) = &args.snapshot_before {
+                snapshot::take(label).into_diagnostic()?;
+            }
+            switch_config(&value, &args.options())
+                .map(|_| println!("Success:   {}  Switched to {value}", "✓".green()))
+                .into_diagnostic()?;
+            if let Some(path) = &args.notify {
+                notify::notify(path, &value);
+            }
+            if args.previous {
+                history::clear_previous().into_diagnostic()?;
+            }
+            Ok(())
+        }
+        Config::Undo => {
+            let value = history::require_previous().into_diagnostic()?;
+            confirm_switch(&value).into_diagnostic()?;
+            switch_config(&value, &SwitchOptions::default())
+                .map(|_| println!("Success:   {}  Switched back to {value}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::List(ListArgs {
+            all,
+            tree,
+            columns,
+            tag,
+            stale,
+            modified_since_switch,
+            json,
+            with_status,
+            output_template,
+        }) => {
+            if let Some(template) = output_template {
+                list::list_templated(&template, &tag, all).into_diagnostic()?;
+                return Ok(());
+            }
+            let json = json.then_some(with_status);
+            list::list_config(
+                columns.as_deref(),
+                &tag,
+                all,
+                tree,
+                stale,
+                modified_since_switch,
+                json,
+            )
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Remove {
+            trash,
+            no_trash,
+            json,
+            ignore_missing,
+            mut values,
+        } => {
+            let trash = trash || (!no_trash && settings::load().into_diagnostic()?.trash_on_remove);
+            if ignore_missing {
+                let mut missing = vec![];
+                for name in &values {
+                    if !config_path(name).into_diagnostic()?.is_file() {
+                        tracing::debug!(name, "config does not exist, skipping it");
+                        missing.push(name.clone());
+                    }
+                }
+                values.retain(|name| !missing.contains(name));
+            }
+            for name in &values {
+                project::warn_linked(name).into_diagnostic()?;
+            }
+            if !trash && !values.is_empty() {
+                confirm_destructive(&format!("permanently delete {}", values.join(", ")))
+                    .into_diagnostic()?;
+            }
+
+            if trash {
+                batch::run_batch(
+                    &values,
+                    json,
+                    |name| format!("Moved {name} to the trash"),
+                    trash::trash_config,
+                )
+            } else {
+                batch::run_batch(
+                    &values,
+                    json,
+                    |name| format!("Removed {name}"),
+                    remove_config,
+                )
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Edit(args) if args.values.len() > 1 => {
+            edit_configs(&args).into_diagnostic()?;
+            println!(
+                "Success:   {}  Opened {} at {}",
+                "✓".green(),
+                args.values.join(", "),
+                args.editor
+            );
+            Ok(())
+        }
+        Config::Edit(args) => {
+            let name = match args.values.first() {
+                Some(name) => name.clone(),
+                None => pick_config("Edit which config?").into_diagnostic()?,
+            };
+            edit_config(&args, &name).into_diagnostic()?;
+            // A scratch edit has already said what became of the changes.
+            if !args.temp {
+                println!(
+                    "Success:   {}  Opened {name} at {}",
+                    "✓".green(),
+                    args.editor
+                );
+            }
+
+            Ok(())
+        }
+        Config::Diff {
+            json,
+            format,
+            base,
+            a,
+            b,
+            ..
+        } => {
+            let format = if json { DiffFormat::Json } else { format };
+            // Without B, clap has made sure --against-cargo was given.
+            match (b, base) {
+                (Some(b), Some(base)) => diff::diff_three_way(&a, &b, &base, format),
+                (Some(b), None) => diff::diff_configs(&a, &b, format),
+                (None, _) => diff::diff_against_live(&a, format),
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Verify {
+            against_cargo,
+            all,
+            jobs,
+            value,
+        } => {
+            if all {
+                verify::verify_all(jobs.unwrap_or_else(parallel::default_jobs))
+            } else {
+                verify::verify_config(value.as_deref(), against_cargo)
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Link {
+            into,
+            symlink,
+            value,
+        } => {
+            let mode = if symlink {
+                LinkMode::Symlink
+            } else {
+                LinkMode::Hard
+            };
+            project::link_into(&value, &project_path(&into).into_diagnostic()?, mode)
+                .map(|target| {
+                    println!(
+                        "Success:   {}  Linked {value} into {}",
+                        "✓".green(),
+                        target.display()
+                    )
+                })
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::RepairLink { force, all } => {
+            if all {
+                links::repair_all(force)
+            } else {
+                links::repair_link(force)
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Links => {
+            project::list_links().into_diagnostic()?;
+            Ok(())
+        }
+        Config::Doctor { fix, json } => {
+            doctor::doctor(fix, json).into_diagnostic()?;
+            Ok(())
+        }
+        Config::SelfCheck { fix } => {
+            self_check::self_check(fix).into_diagnostic()?;
+            Ok(())
+        }
+        Config::VerifyLinks { repair } => {
+            links::verify_links(repair).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Lint { all, jobs, value } => {
+            if all {
+                lint::lint_all(jobs.unwrap_or_else(parallel::default_jobs))
+            } else {
+                lint::lint_config(value.as_deref())
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Migrate { name } => {
+            migrate::migrate_legacy_config(&name).into_diagnostic()?;
+            Ok(())
+        }
+        Config::MigrateFormat { all, value } => {
+            migrate::migrate_configs(value.as_deref(), all).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Rename { force, from, to } => {
+            rename_config(&from, &to, force)
+                .map(|_| println!("Success:   {}  Renamed {from} to {to}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Copy { from, to } => {
+            copy_config(&from, &to)
+                .map(|_| println!("Success:   {}  Copied {from} to {to}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Show {
+            case_insensitive,
+            format,
+            compare_current,
+            resolved,
+            clipboard,
+            value,
+        } => {
+            let value = resolve_name(&value, case_insensitive).into_diagnostic()?;
+            if compare_current {
+                diff::show_against_live(&value)
+            } else {
+                show_config(&value, format, resolved, clipboard)
+            }
+            .into_diagnostic()?;
+            if clipboard {
+                println!(
+                    "Success:   {}  Copied {value} to the clipboard",
+                    "✓".green()
+                );
+            }
+            Ok(())
+        }
+        Config::Stats { json } => {
+            stats::print_stats(json).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Integrity { values } => {
+            checksum::integrity(&values).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Trace => {
+            trace::trace().into_diagnostic()?;
+            Ok(())
+        }
+        Config::Get { effective, key } => {
+            get::get(&key, effective).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Run { value, args } => {
+            let status = run::run_with_config(&value, &args).into_diagnostic()?;
+            if !status.success() {
+                process::exit(status.code().unwrap_or(1));
+            }
+            Ok(())
+        }
+        Config::Assemble { value } => {
+            fragments::assemble(&value)
+                .map(|_| println!("Success:   {}  Assembled {value}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Describe { value, description } => {
+            describe_config(&value, description).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Prompt { .. } | Config::JsonSchema { .. } | Config::Completions { .. } => {
+            unreachable!("handled before initialising")
+        }
+        Config::Disable { value } => {
+            set_disabled(&value, true)
+                .map(|_| println!("Success:   {}  Disabled {value}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Enable { value } => {
+            set_disabled(&value, false)
+                .map(|_| println!("Success:   {}  Enabled {value}", "✓".green()))
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Fragment { unset, value } => {
+            set_fragment(&value, !unset)
+                .map(|_| match unset {
+                    false => println!("Success:   {}  Marked {value} as a fragment", "✓".green()),
+                    true => println!("Success:   {}  {value} is a full config again", "✓".green()),
+                })
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Template { unset, value } => {
+            set_template(&value, !unset)
+                .map(|_| match unset {
+                    false => println!("Success:   {}  Marked {value} as a template", "✓".green()),
+                    true => println!(
+                        "Success:   {}  {value} is a plain config again",
+                        "✓".green()
+                    ),
+                })
+                .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Export(args) => {
+            transfer::export_config(&args).into_diagnostic()?;
+            if let Some(output) = &args.output {
+                println!(
+                    "Success:   {}  Exported {} to {}",
+                    "✓".green(),
+                    args.value,
+                    output.display()
+                );
+            }
+            if args.clipboard {
+                println!(
+                    "Success:   {}  Copied {} to the clipboard",
+                    "✓".green(),
+                    args.value
+                );
+            }
+            Ok(())
+        }
+        Config::Import {
+            name,
+            format,
+            no_follow_symlinks,
+            skip_duplicates,
+            path,
+            ..
+        } => {
+            let imported = transfer::import_config(
+                &path,
+                name.as_deref(),
+                format,
+                !no_follow_symlinks,
+                skip_duplicates,
+            )
+            .into_diagnostic()?;
+            if let Some(name) = imported {
+                println!("Success:   {}  Imported {name}", "✓".green());
+            }
+            Ok(())
+        }
+        Config::Dump {
+            json,
+            bundle,
+            output,
+        } => {
+            state::dump_state(json, bundle, output.as_deref()).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Reset { infer } => {
+            state::reset(infer).into_diagnostic()?;
+            Ok(())
+        }
+        Config::Restore { force, path } => {
+            if force {
+                confirm_destructive("overwrite existing configs with the dump")
+                    .into_diagnostic()?;
+            }
+            state::restore_state(&path, force).into_diagnostic()?;
+            Ok(())
+        }
+        Config::AliasCargo { config, command } => {
+            let config = config.as_deref();
+            match command {
+                AliasCommand::List => alias::list_aliases(config),
+                AliasCommand::Set { name, expansion } => {
+                    alias::set_alias(config, &name, &expansion)
+                }
+                AliasCommand::Remove { name } => alias::remove_alias(config, &name),
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Tag { command } => {
+            match command {
+                TagCommand::Add { value, tags } => tag_config(&value, &tags, true)
+                    .map(|_| println!("Success:   {}  Tagged {value}", "✓".green())),
+                TagCommand::Remove { value, tags } => tag_config(&value, &tags, false)
+                    .map(|_| println!("Success:   {}  Untagged {value}", "✓".green())),
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Tags => {
+            list::list_tags().into_diagnostic()?;
+            Ok(())
+        }
+        Config::Snapshot { command } => {
+            match command {
+                SnapshotCommand::List => snapshot::list_snapshots(),
+                SnapshotCommand::Restore { name, label } => {
+                    snapshot::restore_snapshot(&label, name.as_deref()).map(|name| {
+                        println!(
+                            "Success:   {}  Restored snapshot {label} as {name}",
+                            "✓".green()
+                        )
+                    })
+                }
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Trash { command } => {
+            match command {
+                TrashCommand::List => trash::list_trash(),
+                TrashCommand::Restore { value } => trash::restore_config(&value)
+                    .map(|_| println!("Success:   {}  Restored {value}", "✓".green())),
+                TrashCommand::Empty => {
+                    confirm_destructive("empty the trash").and_then(|_| trash::empty_trash())
+                }
+            }
+            .into_diagnostic()?;
+            Ok(())
+        }
+        Config::Names => {
+            let metas = meta::load().into_diagnostic()?;
+            for name in config_names().into_diagnostic()? {
+                if !metas.get(&name).is_some_and(|meta| meta.disabled) {
+                    println!("{name}");
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Send the operation log to stderr with `--verbose` and to `--log-file` if
+/// given.
+fn init_logging(global: &GlobalArgs) -> io::Result<()> {
+    let stderr = global
+        .verbose
+        .then(|| tracing_subscriber::fmt::layer().with_writer(io::stderr));
+
+    let file = match &global.log_file {
+        Some(path) => {
+            let file = File::options()
+                .create(true)
+                .write(true)
+                .append(global.append_log)
+                .truncate(!global.append_log)
+                .open(path)?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file)),
+            )
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr.with_filter(LevelFilter::DEBUG))
+        .with(file.with_filter(LevelFilter::DEBUG))
+        .init();
+    Ok(())
+}
+
+fn create_config(args: &CreateArgs, name: &str, template: Option<&str>) -> io::Result<()> {
+    check_new_name(name)?;
+
+    let content = match template {
+        // Built from answers, so there are no placeholders to fill in.
+        Some(content) if args.interactive => content.to_string(),
+        Some(template) => {
+            // A template keeps its placeholders to be filled in on switch.
+            let allow_unset = args.allow_unset || args.as_template;
+            let content = template::substitute(template, &args.var, allow_unset)?;
+            // Catch a broken template before it is stored.
+            format::parse(&content, Format::Toml, name)?;
+            content
+        }
+        None => String::new(),
+    };
+
+    check_duplicate(name, &content, args.json)?;
+
+    let path = config_path(name)?;
+    if let Some((group, _)) = name.rsplit_once('/') {
+        create_groups(group, args.parents)?;
+    }
+    tracing::debug!(path = %path.display(), "creating config");
+    let mut file = create_private_file(&path).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{name} already exists"),
+        ),
+        _ => permission_hint(err, &path),
+    })?;
+    file.write_all(content.as_bytes())?;
+    if args.as_template {
+        set_template(name, true)?;
+    }
+    Ok(())
+}
+
+/// Make sure the directory for the config group `group`, such as `work` for
+/// `work/ci`, exists, creating it and any groups above it with `parents`.
+fn create_groups(group: &str, parents: bool) -> io::Result<()> {
+    let mut path = resolve_config_dir()?;
+    for part in group.split('/') {
+        path.push(part);
+        if path.is_dir() {
+            continue;
+        }
+        if !parents {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Group {group} does not exist, pass --parents to create it"),
+            ));
+        }
+        tracing::debug!(path = %path.display(), "creating config group");
+        create_private_dir(&path).map_err(|err| permission_hint(err, &path))?;
+    }
+    Ok(())
+}
+
+/// Link `name` as the live config and record it as the active one.
+fn switch_config(name: &str, options: &SwitchOptions) -> io::Result<()> {
+    if meta::get(name)?.fragment {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{name} is a fragment, include it from a config instead of switching to it"),
+        ));
+    }
+    let previous = current_config()?;
+    let previous_record = history::previous()?;
+    if !options.skip_hooks {
+        hooks::run_hook(Hook::PreSwitch, previous.as_deref(), name)?;
+    }
+
+    if fragments::has_fragments(name)? {
+        fragments::assemble(name)?;
+    }
+    check_linkable(name)?;
+    // Resolved before touching anything, so a broken chain or an unset
+    // placeholder leaves the live config alone.
+    let resolved = template::live_content(name)?;
+
+    let mut path = resolve_config_dir()?;
+    let cargo = match &options.live {
+        Some(live) => {
+            let dir = live.parent().unwrap_or(Path::new("."));
+            if !dir.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not a directory", dir.display()),
+                ));
+            }
+            live.clone()
+        }
+        None => resolve_live_config()?,
+    };
+
+    if cargo.is_dir() {
+        if !options.force {
+            return Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                format!(
+                    "{} is a directory, not a config file. Remove it or rerun with --force",
+                    cargo.display()
+                ),
+            ));
+        }
+
+        let prompt = format!(
+            "{} is a directory, remove it and everything in it?",
+            cargo.display()
+        );
+        if !confirm(&prompt)? {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("Left {} in place, aborting switch", cargo.display()),
+            ));
+        }
+        tracing::debug!(path = %cargo.display(), "removing directory in the way");
+        fs::remove_dir_all(&cargo)?;
+    }
+
+    if !options.keep_current {
+        set_current_config(name)?;
+        if let Some(previous) = previous.as_deref().filter(|previous| *previous != name) {
+            history::record_left(previous)?;
+            if !options.keep_previous {
+                history::set_previous(previous)?;
+            }
+        }
+    } else {
+        tracing::debug!(name, "leaving the current config untouched");
+    }
+
+    if let Err(err) = remove_file(&cargo) {
+        tracing::debug!(path = %cargo.display(), "could not remove live config: {err}");
+    }
+
+    path.push(format!("{name}.toml"));
+
+    match resolved {
+        // Cargo knows nothing of `extends` or `include`, so a composed
+        // config is written out merged instead of linked, as is a rendered
+        // template.
+        Some(content) => {
+            tracing::debug!(name, to = %cargo.display(), "writing resolved config");
+            // It holds everything the stored configs do, tokens included.
+            create_private_file(&cargo)
+                .and_then(|mut file| file.write_all(content.as_bytes()))
+                .map_err(|err| permission_hint(err, &cargo))?;
+        }
+        None => {
+            tracing::debug!(from = %path.display(), to = %cargo.display(), mode = ?options.link, "linking config");
+            link_config(&path, &cargo, options.link).map_err(|err| permission_hint(err, &cargo))?;
+        }
+    }
+
+    if options.skip_hooks {
+        return Ok(());
+    }
+    match hooks::run_hook(Hook::PostSwitch, previous.as_deref(), name) {
+        Ok(()) => Ok(()),
+        Err(err) if options.keep_on_hook_failure => {
+            warning::print(format!("{err}, keeping {name} active"));
+            Ok(())
+        }
+        Err(err) => {
+            let to = roll_back(
+                previous.as_deref(),
+                previous_record.as_deref(),
+                &cargo,
+                options,
+            )
+            .map_err(|rollback| {
+                io::Error::other(format!("{err}, and switching back failed too: {rollback}"))
+            })?;
+            Err(io::Error::other(format!("{err}, switched back to {to}")))
+        }
+    }
+}
+
+/// Remove the live config so cargo falls back to its defaults, and record
+/// that no config is active. The config that was active becomes the previous
+/// one, so `undo` brings it back. Hooks are passed an empty name for the new
+/// config.
+fn switch_to_none(keep_on_hook_failure: bool) -> io::Result<()> {
+    let previous = current_config()?;
+    let previous_record = history::previous()?;
+    hooks::run_hook(Hook::PreSwitch, previous.as_deref(), "")?;
+
+    let cargo = resolve_live_config()?;
+    if cargo.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::IsADirectory,
+            format!(
+                "{} is a directory, not a config file. Remove it yourself",
+                cargo.display()
+            ),
+        ));
+    }
+    tracing::debug!(path = %cargo.display(), "removing live config");
+    match remove_file(&cargo) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            return Err(permission_hint(err, &cargo));
+        }
+        _ => {}
+    }
+    set_current_config("")?;
+    if let Some(previous) = &previous {
+        history::record_left(previous)?;
+        history::set_previous(previous)?;
+    }
+
+    match hooks::run_hook(Hook::PostSwitch, previous.as_deref(), "") {
+        Ok(()) => Ok(()),
+        Err(err) if keep_on_hook_failure => {
+            warning::print(format!("{err}, keeping no config active"));
+            Ok(())
+        }
+        // Nothing was active, so there is nothing to go back to.
+        Err(err) if previous.is_none() => Err(err),
+        Err(err) => {
+            let to = roll_back(
+                previous.as_deref(),
+                previous_record.as_deref(),
+                &cargo,
+                &SwitchOptions::default(),
+            )
+            .map_err(|rollback| {
+                io::Error::other(format!("{err}, and switching back failed too: {rollback}"))
+            })?;
+            Err(io::Error::other(format!("{err}, switched back to {to}")))
+        }
+    }
+}
+
+/// Make sure the stored config for `name` exists and can be opened for
+/// reading. The live config is removed before linking, so a
+/// config that exists but cannot be read (such as after a permission change)
+/// must be caught before anything is touched.
+fn check_linkable(name: &str) -> io::Result<()> {
+    let path = existing_config_path(name)?;
+    File::open(&path).map(drop).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("{name} cannot be read, leaving the live config alone: {err}"),
+        )
+    })
+}
+
+/// Undo a switch whose post-switch hook failed: relink `previous` to
+/// `cargo` (or remove it if no config was active) and restore the active and
+/// previous records. Returns what the live config is now, for the error.
+fn roll_back(
+    previous: Option<&str>,
+    previous_record: Option<&str>,
+    cargo: &Path,
+    options: &SwitchOptions,
+) -> io::Result<String> {
+    let to = match previous {
+        Some(previous) => {
+            tracing::debug!(name = previous, "post-switch hook failed, switching back");
+            switch_config(
+                previous,
+                &SwitchOptions {
+                    keep_previous: true,
+                    skip_hooks: true,
+                    ..options.clone()
+                },
+            )?;
+            previous.to_string()
+        }
+        None => {
+            tracing::debug!("post-switch hook failed, removing the live config");
+            remove_file(cargo)?;
+            if !options.keep_current {
+                set_current_config("")?;
+            }
+            "no config".to_string()
+        }
+    };
+
+    if !options.keep_current && !options.keep_previous {
+        match previous_record {
+            Some(record) => history::set_previous(record)?,
+            None => history::clear_previous()?,
+        }
+    }
+    Ok(to)
+}
+
+fn link_config(path: &Path, cargo: &Path, mode: LinkMode) -> io::Result<()> {
+    match mode {
+        LinkMode::Hard => hard_link(path, cargo),
+        LinkMode::Symlink => symlink_file(path, cargo),
+        LinkMode::RelativeSymlink => {
+            let from = fs::canonicalize(cargo.parent().unwrap_or(Path::new(".")))?;
+            let relative = relative_path(&from, &fs::canonicalize(path)?);
+            symlink_file(&relative, cargo)?;
+
+            // Make sure the link resolves from where it sits.
+            if fs::canonicalize(cargo)? != fs::canonicalize(path)? {
+                remove_file(cargo)?;
+                return Err(io::Error::other(format!(
+                    "{} does not resolve to {} from {}",
+                    relative.display(),
+                    path.display(),
+                    from.display()
+                )));
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(original, link)
+}
+
+/// `to` as a path relative to the directory `from`. Both must be absolute
+/// and free of symlinks, as from `fs::canonicalize`.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from = from.components().collect::<Vec<_>>();
+    let to = to.components().collect::<Vec<_>>();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    path.extend(&to[common..]);
+    path
+}
+
+/// Switches back to the config that was active before a temporary switch
+/// when dropped, so the previous state comes back on every exit path.
+struct RestoreGuard {
+    previous: Option<String>,
+}
+
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        let restored = match &self.previous {
+            Some(previous) => switch_config(
+                previous,
+                &SwitchOptions {
+                    keep_previous: true,
+                    ..SwitchOptions::default()
+                },
+            )
+            .map(|_| println!("Success:   {}  Switched back to {previous}", "✓".green())),
+            None => resolve_live_config().and_then(|cargo| {
+                remove_file(cargo)?;
+                set_current_config("")
+            }),
+        };
+
+        if let Err(err) = restored {
+            warning::print(format!("Could not restore the previous config: {err}"));
+        }
+    }
+}
+
+/// Activate `name` only for the duration of `command`, or an interactive
+/// `$SHELL` if it is empty.
+fn temporary_switch(
+    name: &str,
+    options: &SwitchOptions,
+    command: &[String],
+) -> io::Result<ExitStatus> {
+    let previous = current_config()?;
+    // The switch is undone on exit, so it is not worth remembering.
+    let options = SwitchOptions {
+        keep_previous: true,
+        ..options.clone()
+    };
+    switch_config(name, &options)?;
+    let _guard = RestoreGuard { previous };
+
+    let mut command = match command.split_first() {
+        Some((program, args)) => {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        }
+        None => Command::new(env::var_os("SHELL").unwrap_or("sh".into())),
+    };
+
+    println!(
+        "Success:   {}  Switched to {name} until the command exits",
+        "✓".green()
+    );
+    tracing::debug!(?command, "running command with temporary config");
+    outlive_interrupts();
+    command.status()
+}
+
+/// Keep Ctrl-C and SIGTERM from killing this process, which would skip the
+/// [`RestoreGuard`]. A command in the foreground gets Ctrl-C itself, and once
+/// it exits the previous config is restored as usual.
+fn outlive_interrupts() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let handler = || tracing::debug!("interrupted, waiting for the command to exit");
+        if let Err(err) = ctrlc::set_handler(handler) {
+            tracing::debug!("could not handle interrupts: {err}");
+        }
+    });
+}
+
+fn remove_config(name: &str) -> io::Result<()> {
+    let mut path = resolve_config_dir()?;
+    path.push(format!("{name}.toml"));
+
+    tracing::debug!(path = %path.display(), "removing config");
+    fs::remove_file(path).map_err(|_| {
+        std::io::Error::new(io::ErrorKind::NotFound, format!("{name} does not exist"))
+    })?;
+    meta::remove(name)?;
+    Ok(())
+}
+
+fn rename_config(from: &str, to: &str, force: bool) -> io::Result<()> {
+    check_new_name(to)?;
+    let source = existing_config_path(from)?;
+    let target = config_path(to)?;
+
+    if target.exists() {
+        if !force {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{to} already exists, rerun with --force to replace it"),
+            ));
+        }
+        if !confirm(&format!("{to} already exists, replace it with {from}?"))? {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                format!("Kept {to}, aborting rename"),
+            ));
+        }
+    }
+
+    let live = resolve_live_config()?;
+    let live_link = match fs::read_link(&live) {
+        Ok(link) if link.is_relative() => Some(LinkMode::RelativeSymlink),
+        Ok(_) => Some(LinkMode::Symlink),
+        Err(_) => None,
+    };
+    // A hard link to the replaced config would keep its old contents.
+    let replaces_live = match (fs::metadata(&live), fs::metadata(&target)) {
+        (Ok(live), Ok(target)) => live_link.is_none() && links::same_file(&live, &target),
+        _ => false,
+    };
+
+    // Renaming keeps the inode, so a hard link in the cargo dir stays valid
+    // and only the bookkeeping and symlinks need to follow.
+    tracing::debug!(from = %source.display(), to = %target.display(), "renaming config");
+    fs::rename(source, &target)?;
+    meta::rename(from, to)?;
+    project::rename(from, to)?;
+
+    if current_config()?.as_deref() == Some(from) {
+        set_current_config(to)?;
+        if let Some(mode) = live_link {
+            relink_live(&target, &live, mode)?;
+        }
+    }
+    if replaces_live {
+        relink_live(&target, &live, LinkMode::Hard)?;
+    }
+    Ok(())
+}
+
+/// Point `live` at `path` without a moment where cargo finds no config: the
+/// new link is made next to it and renamed over it.
+fn relink_live(path: &Path, live: &Path, mode: LinkMode) -> io::Result<()> {
+    let mut staged = live.as_os_str().to_owned();
+    staged.push(".tmp");
+    let staged = PathBuf::from(staged);
+
+    if fs::symlink_metadata(&staged).is_ok() {
+        remove_file(&staged)?;
+    }
+    tracing::debug!(path = %path.display(), live = %live.display(), ?mode, "relinking live config");
+    link_config(path, &staged, mode).map_err(|err| permission_hint(err, &staged))?;
+    fs::rename(&staged, live)
+}
+
+/// Print a config in `format`, or copy it to the clipboard with `clipboard`.
+/// With `resolved`, it is what switching to it would put in the live config.
+fn show_config(name: &str, format: Format, resolved: bool, clipboard: bool) -> io::Result<()> {
+    let stored = existing_config_path(name)?;
+    let live = match resolved {
+        true => template::live_content(name)?,
+        false => None,
+    };
+    let mut content = match live {
+        Some(content) => content,
+        None => fs::read_to_string(stored)?,
+    };
+    if format != Format::Toml {
+        let table = format::parse(&content, Format::Toml, name)?;
+        content = format::serialize(&table, format)?;
+    }
+    match clipboard {
+        true => clipboard::copy(&content),
+        false => {
+            print!("{content}");
+            Ok(())
+        }
+    }
+}
+
+fn describe_config(name: &str, description: Option<String>) -> io::Result<()> {
+    existing_config_path(name)?;
+    let mut meta = meta::get(name)?;
+
+    match description {
+        Some(description) => {
+            meta.description = (!description.is_empty()).then_some(description);
+            meta::set(name, meta)?;
+            println!("Success:   {}  Updated description of {name}", "✓".green());
+        }
+        None => println!("{}", meta.description.unwrap_or_default()),
+    }
+    Ok(())
+}
+
+/// Print the active config through `format`. Reads the current file directly,
+/// without checking the config directory, to keep it to a single read.
+fn prompt(format: &str) -> io::Result<()> {
+    let mut path = resolve_cargo_dir()?;
+    path.push("cargo-config/cargo-config-current");
+
+    let name = match fs::read_to_string(&path) {
+        Ok(name) => name,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if !name.is_empty() {
+        println!("{}", format.replace("{name}", &name));
+    }
+    Ok(())
+}
+
+fn set_disabled(name: &str, disabled: bool) -> io::Result<()> {
+    existing_config_path(name)?;
+    let mut meta = meta::get(name)?;
+    meta.disabled = disabled;
+    meta::set(name, meta)
+}
+
+fn set_fragment(name: &str, fragment: bool) -> io::Result<()> {
+    existing_config_path(name)?;
+    let mut meta = meta::get(name)?;
+    meta.fragment = fragment;
+    meta::set(name, meta)
+}
+
+fn set_template(name: &str, template: bool) -> io::Result<()> {
+    existing_config_path(name)?;
+    let mut meta = meta::get(name)?;
+    meta.template = template;
+    meta::set(name, meta)
+}
+
+/// Add `tags` to `name`, or remove them. Removing a tag the config does not
+/// have is an error, so typos do not pass silently.
+fn tag_config(name: &str, tags: &[String], add: bool) -> io::Result<()> {
+    existing_config_path(name)?;
+    let mut meta = meta::get(name)?;
+    for tag in tags {
+        if add {
+            meta.tags.insert(tag.clone());
+        } else if !meta.tags.remove(tag) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{name} is not tagged {tag}"),
+            ));
+        }
+    }
+    meta::set(name, meta)
+}
+
+/// Refuse to switch to a disabled config unless `force` is set, in which case
+/// only warn.
+fn check_enabled(name: &str, force: bool) -> io::Result<()> {
+    if !meta::get(name)?.disabled {
+        return Ok(());
+    }
+
+    if !force {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{name} is disabled, enable it or rerun with --force"),
+        ));
+    }
+    warning::print(format!("{name} is disabled"));
+    Ok(())
+}
+
+fn copy_config(from: &str, to: &str) -> io::Result<()> {
+    check_new_name(to)?;
+    let source = existing_config_path(from)?;
+    let target = config_path(to)?;
+
+    tracing::debug!(from = %source.display(), to = %target.display(), "copying config");
+    let mut contents = File::open(source)?;
+    let mut file = create_private_file(&target).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => {
+            io::Error::new(io::ErrorKind::AlreadyExists, format!("{to} already exists"))
+        }
+        _ => err,
+    })?;
+    io::copy(&mut contents, &mut file)?;
+    Ok(())
+}
+
+fn edit_config(args: &EditArgs, name: &str) -> io::Result<()> {
+    let mut config_dir = resolve_config_dir()?;
+    config_dir.push(format!("{name}.toml"));
+
+    let before = args
+        .diff
+        .then(|| fs::read_to_string(&config_dir))
+        .transpose()?;
 
-#[derive(Parser, Debug)]
-#[command(version, about = "Switch cargo configurations with ease.")]
-enum Config {
-    /// Create a new cargo config
-    Create { value: String },
-    /// Switch between cargo configs
-    Switch { value: String },
-    /// List configs
-    List,
-    /// Remove a config
-    Remove { value: String },
-    /// Launch an editor to edit a config
-    Edit {
-        #[arg(short, long)]
-        editor: String,
-        value: String,
-    },
+    let ed = find_editor(&args.editor)?;
+
+    if args.temp {
+        return edit_temp_copy(args, name, &config_dir, &ed);
+    }
+
+    tracing::debug!(editor = %ed.display(), path = %config_dir.display(), "launching editor");
+    let child = Command::new(&ed).arg(&config_dir).spawn()?;
+    if args.wait_for.is_none() && !args.diff {
+        return Ok(());
+    }
+    wait_for_editor(args, child)?;
+
+    if !recheck_edit(args, &ed, &config_dir, || {
+        verify::parse_config(name).map(drop)
+    })? {
+        return Ok(());
+    }
+
+    if let Some(before) = before {
+        let after = fs::read_to_string(&config_dir)?;
+        diff::render(&before, &after, &format!("{name} (before)"), name);
+    }
+
+    Ok(())
 }
 
-fn main() -> miette::Result<()> {
-    initialise().into_diagnostic()?;
-    let cfg = Config::parse();
+fn find_editor(editor: &str) -> io::Result<PathBuf> {
+    which::which(editor).map_err(|err| match err {
+        which::Error::CannotFindBinaryPath => {
+            io::Error::new(io::ErrorKind::NotFound, err.to_string())
+        }
+        which::Error::CannotGetCurrentDirAndPathListEmpty => {
+            io::Error::new(io::ErrorKind::PermissionDenied, err.to_string())
+        }
+        which::Error::CannotCanonicalize => io::Error::other(err.to_string()),
+    })
+}
 
-    match cfg {
-        Config::Create { value } => {
-            create_config(&value)
-                .and_then(|_| Ok(println!("Success:   {}  Created {value}.toml", "✓".green())))
-                .into_diagnostic()?;
-            Ok(())
+/// Open every config in `args.values` in a single editor, checking they all
+/// exist first. Waiting, rechecking and `--diff` work as for a single config,
+/// config by config once the editor is done.
+fn edit_configs(args: &EditArgs) -> io::Result<()> {
+    if args.temp {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--temp edits a single config at a time",
+        ));
+    }
+    let paths = args
+        .values
+        .iter()
+        .map(|name| existing_config_path(name))
+        .collect::<io::Result<Vec<_>>>()?;
+    let before = args
+        .diff
+        .then(|| {
+            paths
+                .iter()
+                .map(fs::read_to_string)
+                .collect::<io::Result<Vec<_>>>()
+        })
+        .transpose()?;
+
+    let ed = find_editor(&args.editor)?;
+    let mut command = Command::new(&ed);
+    command.args(layout_flags(args, &ed)).args(&paths);
+    tracing::debug!(?command, "launching editor");
+    let child = command.spawn()?;
+    if args.wait_for.is_none() && !args.diff {
+        return Ok(());
+    }
+    wait_for_editor(args, child)?;
+
+    for (i, (name, path)) in args.values.iter().zip(&paths).enumerate() {
+        if !recheck_edit(args, &ed, path, || verify::parse_config(name).map(drop))? {
+            continue;
         }
-        Config::Switch { value } => {
-            switch_config(&value)
-                .and_then(|_| Ok(println!("Success:   {}  Switched to {value}", "✓".green())))
-                .into_diagnostic()?;
-            Ok(())
+        if let Some(before) = &before {
+            let after = fs::read_to_string(path)?;
+            diff::render(&before[i], &after, &format!("{name} (before)"), name);
         }
-        Config::List => {
-            list_config().into_diagnostic()?;
-            Ok(())
+    }
+    Ok(())
+}
+
+/// The flags that make `editor` open its files in tabs or splits, as asked.
+/// Only the vi family is known, other editors get a warning and open the
+/// files their usual way.
+fn layout_flags(args: &EditArgs, editor: &Path) -> Vec<&'static str> {
+    if !args.tabs && !args.split {
+        return vec![];
+    }
+    let stem = editor.file_stem().unwrap_or_default().to_string_lossy();
+    match stem.as_ref() {
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" => {
+            vec![if args.tabs { "-p" } else { "-O" }]
         }
-        Config::Remove { value } => {
-            remove_config(&value)
-                .and_then(|_| Ok(println!("Success:   {}  Removed {value}", "✓".green())))
-                .into_diagnostic()?;
-            Ok(())
+        _ => {
+            warning::print(format!(
+                "Do not know how to open {} in {stem}, opening the configs as usual",
+                if args.tabs { "tabs" } else { "splits" }
+            ));
+            vec![]
         }
-        Config::Edit { editor, value } => {
-            edit_config(&editor, &value)
-                .and_then(|_| {
-                    Ok(println!(
-                        "Success:   {}  Opened {value} at {editor}",
-                        "✓".green()
-                    ))
-                })
-                .into_diagnostic()?;
+    }
+}
 
-            Ok(())
+/// Wait for an edit to finish: for `--wait-for` to settle if given, else for
+/// the editor to exit.
+fn wait_for_editor(args: &EditArgs, mut child: process::Child) -> io::Result<()> {
+    match &args.wait_for {
+        Some(path) => wait_for_change(path, Duration::from_millis(args.settle_ms)),
+        None => child.wait().map(drop),
+    }
+}
+
+/// Warn while `check` fails and offer to reopen `editor` on `path` to fix it,
+/// so an invalid config, possibly the active one, is not left behind after a
+/// typo. Returns whether the file ended up valid. Without a terminal to ask,
+/// or with `--no-recheck`, it only warns.
+fn recheck_edit(
+    args: &EditArgs,
+    editor: &Path,
+    path: &Path,
+    check: impl Fn() -> io::Result<()>,
+) -> io::Result<bool> {
+    loop {
+        let Err(err) = check() else {
+            return Ok(true);
+        };
+        warning::print(err);
+
+        // Not a confirmation, so --yes cannot reopen the editor forever.
+        if args.no_recheck || !can_prompt() || !ask_yes("Reopen the editor to fix it?")? {
+            return Ok(false);
         }
+        tracing::debug!(editor = %editor.display(), path = %path.display(), "reopening editor");
+        wait_for_editor(args, Command::new(editor).arg(path).spawn()?)?;
     }
 }
 
-fn create_config(name: &str) -> io::Result<()> {
-    let mut path = resolve_config_dir()?;
+/// A scratch copy of a config in a private directory of its own, both
+/// deleted when dropped.
+struct TempCopy {
+    dir: PathBuf,
+    path: PathBuf,
+}
 
-    path.push(format!("{name}.toml"));
-    File::create_new(path)?;
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            tracing::debug!(path = %self.dir.display(), "could not remove scratch copy: {err}");
+        }
+    }
+}
+
+/// Open `editor` on a copy of the config at `path` and, once it exits, show
+/// the changes and ask whether to save them. Declining, or a non-interactive
+/// stdin without `--yes`, discards them.
+fn edit_temp_copy(args: &EditArgs, name: &str, path: &Path, editor: &Path) -> io::Result<()> {
+    let before = fs::read_to_string(path)
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, format!("{name} does not exist")))?;
+
+    // The copy may hold registry tokens, so it goes in a new private
+    // directory rather than straight into the shared temp dir, where a
+    // planted file or symlink could be waiting for it.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let mut dir = env::temp_dir();
+    dir.push(format!("cargo-config-edit-{}-{nanos:09}", process::id()));
+    create_private_dir(&dir).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not create {}: {err}", dir.display()),
+        )
+    })?;
+    let scratch = dir.join(format!("{}.toml", name.replace('/', "-")));
+    let copy = TempCopy { dir, path: scratch };
+    create_private_file(&copy.path)?.write_all(before.as_bytes())?;
+
+    tracing::debug!(editor = %editor.display(), path = %copy.path.display(), "launching editor");
+    wait_for_editor(args, Command::new(editor).arg(&copy.path).spawn()?)?;
+    recheck_edit(args, editor, &copy.path, || {
+        let content = fs::read_to_string(&copy.path)?;
+        content.parse::<toml::Table>().map(drop).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The edited copy is not valid TOML: {err}"),
+            )
+        })
+    })?;
+
+    let after = fs::read_to_string(&copy.path)?;
+    if after == before {
+        println!("Success:   {}  No changes to {name}", "✓".green());
+        return Ok(());
+    }
+
+    diff::render(&before, &after, name, &format!("{name} (edited)"));
+
+    if confirm(&format!("Save the changes to {name}.toml?"))? {
+        // Written in place so the live config's hard link keeps pointing at it.
+        fs::write(path, after)?;
+        println!("Success:   {}  Saved the changes to {name}", "✓".green());
+    } else {
+        println!(
+            "Success:   {}  Discarded the changes to {name}",
+            "✓".green()
+        );
+    }
     Ok(())
 }
 
-fn switch_config(name: &str) -> io::Result<()> {
-    let mut path = resolve_config_dir()?;
-    let mut cargo_config_current = path.clone();
-    cargo_config_current.push("cargo-config-current");
+/// Block until `path` has been modified and then left untouched for `settle`.
+fn wait_for_change(path: &Path, settle: Duration) -> io::Result<()> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
 
-    if File::open(&cargo_config_current).is_err() {
-        File::create(&cargo_config_current)?;
+    let mut last = modified(path);
+    let mut changed_at = None;
+
+    loop {
+        thread::sleep(Duration::from_millis(100));
+
+        let now = modified(path);
+        if now != last {
+            last = now;
+            changed_at = Some(Instant::now());
+        } else if changed_at.is_some_and(|at: Instant| at.elapsed() >= settle) {
+            return Ok(());
+        }
     }
+}
 
-    let mut current = File::options().write(true).open(&cargo_config_current)?;
+/// Whether there is anyone to ask: stdin is a terminal, or
+/// `--force-interactive` says to read the answers from it anyway.
+fn can_prompt() -> bool {
+    global().force_interactive || io::stdin().is_terminal()
+}
 
-    write!(&mut current, "{name}")?;
+/// Ask a yes or no question, where anything but yes is no.
+fn ask_yes(prompt: &str) -> io::Result<bool> {
+    print!("{prompt} [y/N] ");
+    io::stdout().flush()?;
 
-    let mut cargo = resolve_cargo_dir()?;
-    cargo.push("config.toml");
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
 
-    let _ = remove_file(&cargo);
+/// Ask to go ahead with `prompt`. Every confirmation goes through here, so
+/// `--yes` answers yes to all of them, and without anyone to ask the answer
+/// is no.
+fn confirm(prompt: &str) -> io::Result<bool> {
+    if global().yes {
+        return Ok(true);
+    }
+    if !can_prompt() {
+        tracing::debug!(prompt, "no terminal to ask on, assuming no");
+        return Ok(false);
+    }
+    ask_yes(prompt)
+}
 
-    path.push(format!("{name}.toml"));
+/// Under `--confirm-destructive` (or the setting), ask before `action`
+/// unless `--yes` was given.
+fn confirm_destructive(action: &str) -> io::Result<()> {
+    let global = global();
+    if !(global.confirm_destructive || settings::load()?.confirm_destructive) {
+        return Ok(());
+    }
+    require_confirmation(action)
+}
 
-    hard_link(path, cargo)?;
+/// Ask before `action` unless `--yes` was given. Without a terminal to ask
+/// on, this errors instead of going ahead.
+fn require_confirmation(action: &str) -> io::Result<()> {
+    if !global().yes && !can_prompt() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Refusing to {action} without confirmation, pass --yes"),
+        ));
+    }
+    if !confirm(&format!("This will {action}, continue?"))? {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            format!("Did not {action}"),
+        ));
+    }
     Ok(())
 }
 
-fn list_config() -> io::Result<()> {
-    let path = resolve_config_dir()?;
-    let mut current = String::new();
+/// Confirm replacing the live config with `name`, if there is one.
+fn confirm_switch(name: &str) -> io::Result<()> {
+    if fs::symlink_metadata(resolve_live_config()?).is_err() {
+        return Ok(());
+    }
+    confirm_destructive(&format!("replace the live config with {name}"))
+}
 
-    let mut cargo_config_current = path.clone();
-    cargo_config_current.push("cargo-config-current");
+/// Ask which stored config to use from a numbered list, marking the active
+/// one. Errors when stdin is not a terminal, since nobody could answer.
+fn pick_config(prompt: &str) -> io::Result<String> {
+    if !can_prompt() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No config name given and stdin is not a terminal to pick one",
+        ));
+    }
 
-    File::open(&cargo_config_current)?.read_to_string(&mut current)?;
-
-    fs::read_dir(path).and_then(|entry| {
-        println!("List of profiles:");
-        for e in entry {
-            if let Ok(entry) = e {
-                let os_file_name = entry.file_name();
-                let file_name = os_file_name.to_string_lossy();
-                let names = file_name.split(".toml").collect::<Vec<&str>>();
-                let name = names[0];
-
-                if name != "cargo-config-current" {
-                    if name == &current {
-                        println!("- {} (current)", name)
-                    } else {
-                        println!("- {}", name)
-                    }
-                }
-            }
+    let names = config_names()?;
+    if names.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "There are no configs, create one first",
+        ));
+    }
+
+    let current = current_config()?;
+    for (i, name) in names.iter().enumerate() {
+        if current.as_deref() == Some(name.as_str()) {
+            println!("{:>3}) {name} (current)", i + 1);
+        } else {
+            println!("{:>3}) {name}", i + 1);
         }
-        Ok(())
-    })?;
+    }
+    print!("{prompt} [1-{}] ", names.len());
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|choice| names.get(choice.checked_sub(1)?))
+        .cloned()
+        .ok_or(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not one of the listed configs", answer.trim()),
+        ))
+}
 
+/// The stored config other than `name` whose content hashes the same as
+/// `content`, if any. Empty configs are all alike, so they never match.
+fn find_duplicate(name: &str, content: &str) -> io::Result<Option<String>> {
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    let hash = checksum::hash(content.as_bytes());
+    Ok(config_names()?.into_iter().find(|existing| {
+        existing != name
+            && config_path(existing)
+                .and_then(fs::read)
+                .is_ok_and(|existing| checksum::hash(&existing) == hash)
+    }))
+}
+
+/// Warn when `content` for the new config `name` is byte-identical to an
+/// existing config, or refuse under `--dedupe-on-create`. With `json`, the
+/// warning goes to stderr so stdout stays parseable.
+fn check_duplicate(name: &str, content: &str, json: bool) -> io::Result<()> {
+    let Some(existing) = find_duplicate(name, content)? else {
+        return Ok(());
+    };
+
+    if global().dedupe_on_create {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{name} would be identical to {existing}, use {existing} instead"),
+        ));
+    }
+    let message = format!("{name} is identical to {existing}");
+    match json {
+        true => warning::eprint(message),
+        false => warning::print(message),
+    }
     Ok(())
 }
 
-fn remove_config(name: &str) -> io::Result<()> {
-    let mut path = resolve_config_dir()?;
-    path.push(format!("{name}.toml"));
+/// Clean up a config name given on the command line, trimming whitespace a
+/// script or a copy-paste may have left around it. Names may be namespaced
+/// with `/`, as in `work/ci`, but must stay inside the config directory and
+/// out of the hidden and `.d` fragment directories kept there.
+fn parse_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("config names cannot be empty".to_string());
+    }
+    if name.contains('\\') || Path::new(name).has_root() {
+        return Err(format!("{name} is not a valid config name"));
+    }
+    let (groups, last) = name.rsplit_once('/').unwrap_or(("", name));
+    let parts = groups.split('/').filter(|_| !groups.is_empty());
+    for part in parts.clone().chain([last]) {
+        if part.is_empty() || part.starts_with('.') {
+            return Err(format!(
+                "{name} is not a valid config name, no part of it may be empty or start with ."
+            ));
+        }
+    }
+    if parts.clone().any(|part| part.ends_with(".d")) {
+        return Err(format!(
+            "{name} is not a valid config name, .d directories hold fragments"
+        ));
+    }
+    Ok(name.to_string())
+}
 
-    fs::remove_file(path).map_err(|_| {
-        std::io::Error::new(io::ErrorKind::NotFound, format!("{name} does not exist"))
+/// Check a directory given on the command line exists.
+fn parse_dir(dir: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(dir);
+    if !path.is_dir() {
+        return Err(format!("{dir} is not a directory"));
+    }
+    Ok(path)
+}
+
+/// The directory project paths are relative to: `--cwd` if given, else the
+/// working directory.
+fn project_dir() -> io::Result<PathBuf> {
+    match &global().cwd {
+        Some(dir) => env::current_dir().map(|cwd| cwd.join(dir)),
+        None => env::current_dir(),
+    }
+}
+
+/// `path` resolved against [`project_dir`] if it is relative.
+fn project_path(path: &Path) -> io::Result<PathBuf> {
+    Ok(project_dir()?.join(path))
+}
+
+/// Check a tag given on the command line. Tags are single words, since
+/// `--columns tags` joins them with commas.
+fn parse_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(format!("{tag:?} is not a valid tag"));
+    }
+    Ok(tag.to_string())
+}
+
+/// Names kept for the tool's own use: `config` is what the first run stores
+/// an existing live config as, and `hooks` is the hook script directory.
+/// Names starting with `cargo-config-`, like the bookkeeping files, are kept
+/// too.
+const RESERVED_NAMES: &[&str] = &["config", "hooks"];
+
+/// Make sure a name for a new config does not clash with the tool's own
+/// files, or with an existing config by case alone, which would be the same
+/// file on case-insensitive filesystems, unless `--allow-reserved` (or the
+/// allow-reserved setting) is given. Then, under `--strict-names` (or the
+/// strict-names setting), make sure it matches the configured pattern.
+fn check_new_name(name: &str) -> io::Result<()> {
+    let settings = settings::load()?;
+    if !global().allow_reserved && !settings.allow_reserved {
+        check_reserved(name)?;
+    }
+    if !global().strict_names && !settings.strict_names {
+        return Ok(());
+    }
+
+    let pattern = settings
+        .name_pattern
+        .as_deref()
+        .unwrap_or(settings::DEFAULT_NAME_PATTERN);
+    let regex = Regex::new(pattern).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("name-pattern setting is not a valid regex: {err}"),
+        )
     })?;
+
+    if !regex.is_match(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{name} does not match the required name pattern {pattern}"),
+        ));
+    }
     Ok(())
 }
 
-fn edit_config(editor: &str, name: &str) -> io::Result<()> {
-    let mut config_dir = resolve_config_dir()?;
-    config_dir.push(format!("{name}.toml"));
+fn check_reserved(name: &str) -> io::Result<()> {
+    let first = name.split('/').next().unwrap_or(name).to_lowercase();
+    if RESERVED_NAMES.contains(&first.as_str()) || first.starts_with("cargo-config-") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{name} is reserved for the tool's own files, pick another name or pass --allow-reserved"
+            ),
+        ));
+    }
 
-    let ed = which::which(editor).map_err(|err| match err {
-        which::Error::CannotFindBinaryPath => {
-            io::Error::new(io::ErrorKind::NotFound, err.to_string())
-        }
-        which::Error::CannotGetCurrentDirAndPathListEmpty => {
-            io::Error::new(io::ErrorKind::PermissionDenied, err.to_string())
-        }
-        which::Error::CannotCanonicalize => io::Error::new(io::ErrorKind::Other, err.to_string()),
+    let clash = config_names()?
+        .into_iter()
+        .find(|existing| existing != name && existing.to_lowercase() == name.to_lowercase());
+    if let Some(existing) = clash {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "{name} differs from {existing} only by case, which is the same file on \
+                 case-insensitive filesystems. Pick another name or pass --allow-reserved"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Find the stored config `name` refers to. With `case_insensitive`, a name
+/// that is not an exact match resolves to the one config matching it ignoring
+/// case.
+fn resolve_name(name: &str, case_insensitive: bool) -> io::Result<String> {
+    if !case_insensitive || config_path(name)?.is_file() {
+        return Ok(name.to_string());
+    }
+
+    let matches = config_names()?
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase() == name.to_lowercase())
+        .collect::<Vec<_>>();
+
+    match matches.as_slice() {
+        [] => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{name} does not exist"),
+        )),
+        [single] => Ok(single.clone()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{name} matches several configs ignoring case: {}",
+                matches.join(", ")
+            ),
+        )),
+    }
+}
+
+/// Names of every stored config, read fresh from the config directory.
+fn config_names() -> io::Result<Vec<String>> {
+    let mut names = store_files(&resolve_config_dir()?)?
+        .into_iter()
+        .filter_map(|(file_name, _)| Some(file_name.strip_suffix(".toml")?.to_string()))
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+/// Every file under `dir`, named by its path relative to `dir` with `/`
+/// between groups, as in `work/ci.toml`. Hidden directories and `.d`
+/// fragment directories are not entered. Entries that cannot be read are
+/// reported on stderr, where they cannot break `--json` output, and skipped.
+fn store_files(dir: &Path) -> io::Result<Vec<(String, fs::DirEntry)>> {
+    let read = fs::read_dir(dir).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not read config directory {}: {err}", dir.display()),
+        )
     })?;
 
-    Command::new(ed).arg(config_dir).spawn()?;
+    let mut files = vec![];
+    let mut groups = vec![(String::new(), read)];
+    while let Some((prefix, read)) = groups.pop() {
+        for entry in read {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warning::eprint(format!(
+                        "Skipped an unreadable entry in {}: {err}",
+                        dir.display()
+                    ));
+                    continue;
+                }
+            };
+            let file_name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+            if !entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                files.push((file_name, entry));
+                continue;
+            }
+            if file_name.ends_with(".d") || entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            match fs::read_dir(entry.path()) {
+                Ok(read) => groups.push((format!("{file_name}/"), read)),
+                Err(err) => {
+                    warning::eprint(format!("Skipped {file_name}, it could not be read: {err}"))
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn config_path(name: &str) -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push(format!("{name}.toml"));
+    Ok(path)
+}
 
-    Ok(())
+/// Like [`config_path`], but errors if the config does not exist.
+fn existing_config_path(name: &str) -> io::Result<PathBuf> {
+    let path = config_path(name)?;
+    if !path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{name} does not exist"),
+        ));
+    }
+    Ok(path)
+}
+
+fn set_current_config(name: &str) -> io::Result<()> {
+    let mut cargo_config_current = resolve_config_dir()?;
+    cargo_config_current.push("cargo-config-current");
+    tracing::debug!(name, "recording active config");
+    fs::write(&cargo_config_current, name)
+        .map_err(|err| permission_hint(err, &cargo_config_current))
+}
+
+fn current_config() -> io::Result<Option<String>> {
+    let mut cargo_config_current = resolve_config_dir()?;
+    cargo_config_current.push("cargo-config-current");
+
+    match fs::read_to_string(cargo_config_current) {
+        Ok(current) if !current.is_empty() => Ok(Some(current)),
+        Ok(_) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
 }
 
 fn resolve_config_dir() -> io::Result<PathBuf> {
-    let mut path = simple_home_dir::home_dir().ok_or(std::io::Error::new(
-        std::io::ErrorKind::NotFound,
-        "Cargo directory could not be found",
-    ))?;
+    let mut path = resolve_cargo_dir()?;
+    path.push("cargo-config/");
 
-    path.push(".cargo/cargo-config/");
-    let _ = fs::create_dir(&path);
+    if global().no_create_dir || global().read_only {
+        if !path.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Config directory {} does not exist, create a config first",
+                    path.display()
+                ),
+            ));
+        }
+    } else if let Err(err) = create_private_dir(&path) {
+        if err.kind() == io::ErrorKind::PermissionDenied {
+            return Err(permission_hint(err, &path));
+        }
+    }
     Ok(path)
 }
 
+/// `--target-cargo-home` if given, otherwise `~/.cargo`.
 fn resolve_cargo_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = &global().target_cargo_home {
+        return Ok(dir.clone());
+    }
+
     let mut path = simple_home_dir::home_dir().ok_or(std::io::Error::new(
         std::io::ErrorKind::NotFound,
         "Cargo directory could not be found",
@@ -185,30 +2857,279 @@ fn resolve_cargo_dir() -> io::Result<PathBuf> {
     Ok(path)
 }
 
-fn initialise() -> io::Result<()> {
+/// Explain a permission error on `path`, which usually means the cargo home
+/// belongs to another user, such as root on a shared build server. Other
+/// errors are passed through untouched.
+fn permission_hint(err: io::Error, path: &Path) -> io::Error {
+    if err.kind() != io::ErrorKind::PermissionDenied {
+        return err;
+    }
+
+    io::Error::new(
+        err.kind(),
+        format!(
+            "Permission denied writing {}. The cargo home is likely owned by another user; \
+             rerun as that user (for example with sudo), or pass --target-cargo-home to \
+             manage a cargo home you can write to",
+            path.display()
+        ),
+    )
+}
+
+/// Parse an octal permission mode such as `700` or `0o700`.
+fn parse_mode(mode: &str) -> Result<u32, String> {
+    let digits = mode.strip_prefix("0o").unwrap_or(mode);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("expected an octal mode such as 700, got {mode}")),
+    }
+}
+
+/// Create the config directory, readable only by its owner unless
+/// `--dir-mode` says otherwise. On Windows, the default ACLs apply.
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(global().dir_mode.unwrap_or(0o700));
+    }
+    builder.create(path)
+}
+
+/// Create a new stored config, failing if it exists. Configs may hold
+/// registry tokens, so they are readable only by their owner unless
+/// `--file-mode` says otherwise. Both modes are still subject to the umask.
+fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(global().file_mode.unwrap_or(0o600));
+    }
+    options.open(path)
+}
+
+/// The file cargo reads the active config from: `--config-target` (or
+/// `CARGO_CONFIG_TARGET`) if set, otherwise `config.toml` in the cargo dir.
+fn resolve_live_config() -> io::Result<PathBuf> {
+    let mut path = resolve_cargo_dir()?;
+    // Pushing an absolute path replaces the cargo dir entirely.
+    path.push(
+        global()
+            .config_target
+            .as_deref()
+            .unwrap_or(Path::new("config.toml")),
+    );
+    Ok(path)
+}
+
+/// Print where the tool looks for everything, for `--print-dir`. Nothing is
+/// created, missing directories are only pointed out.
+fn print_dirs() -> io::Result<()> {
+    for line in dir_lines()? {
+        eprintln!("{line}");
+    }
+    Ok(())
+}
+
+/// The lines `--print-dir` prints, also kept in `--report-file` bundles.
+fn dir_lines() -> io::Result<[String; 3]> {
+    let cargo = resolve_cargo_dir()?;
+    let config_dir = cargo.join("cargo-config");
+    let live = resolve_live_config()?;
+    let missing = |path: &Path| if path.exists() { "" } else { " (missing)" };
+
+    let cargo_source = match global().target_cargo_home {
+        Some(_) => " (from --target-cargo-home)",
+        None => "",
+    };
+    // The flag wins over the variable, so a target equal to the variable
+    // came from it.
+    let live_source = match (&global().config_target, env::var_os("CARGO_CONFIG_TARGET")) {
+        (None, _) => "",
+        (Some(target), Some(var)) if target.as_os_str() == var => " (from CARGO_CONFIG_TARGET)",
+        (Some(_), _) => " (from --config-target)",
+    };
+
+    Ok([
+        format!(
+            "Cargo dir:   {}{cargo_source}{}",
+            cargo.display(),
+            missing(&cargo)
+        ),
+        format!(
+            "Config dir:  {}{}",
+            config_dir.display(),
+            missing(&config_dir)
+        ),
+        format!(
+            "Live config: {}{live_source}{}",
+            live.display(),
+            missing(&live)
+        ),
+    ])
+}
+
+/// What `initialise` did before running a command.
+#[derive(Debug, Default)]
+struct InitReport {
+    /// An unmanaged live config moved into the store, from and to.
+    moved: Option<(PathBuf, PathBuf)>,
+    /// The config switched to after moving it.
+    switched_to: Option<String>,
+}
+
+impl InitReport {
+    fn is_empty(&self) -> bool {
+        self.moved.is_none() && self.switched_to.is_none()
+    }
+
+    fn print(&self) {
+        if let Some((from, to)) = &self.moved {
+            println!(
+                "Moved:     {}  {} to {}",
+                "→".cyan(),
+                from.display(),
+                to.display()
+            );
+        }
+        if let Some(name) = &self.switched_to {
+            println!("Switched:  {}  {name}", "→".cyan());
+        }
+    }
+}
+
+/// Take over an existing live config on first run, moving it into the store
+/// as `config` and switching to it. Read-only and `--no-create-dir` runs only
+/// warn about it.
+fn initialise() -> io::Result<InitReport> {
+    let mut report = InitReport::default();
     let mut cargo_config_current = resolve_config_dir()?;
+    tracing::debug!(
+        config_dir = %cargo_config_current.display(),
+        cargo_dir = %resolve_cargo_dir()?.display(),
+        live_config = %resolve_live_config()?.display(),
+        "resolved directories"
+    );
     cargo_config_current.push("cargo-config-current");
 
     if File::open(&cargo_config_current).is_err() {
-        let mut current_path = resolve_cargo_dir()?;
-        current_path.push("config.toml");
+        let current_path = resolve_live_config()?;
 
         if let Ok(mut cfg) = File::open(&current_path) {
-            println!(
-            "Warning:   {}  config.toml exists in Cargo directory, moving to cargo-config/config.toml",
-            "⚠".yellow()
-        );
+            // Taking over writes to the store and the live config, which
+            // read-only commands must not do. Stderr keeps their output clean.
+            if global().read_only || global().no_create_dir {
+                warning::eprint(format!(
+                    "{} is not managed yet, it is taken over by the next command that may write",
+                    current_path.display()
+                ));
+                return Ok(report);
+            }
             let mut tmp = vec![];
             let mut mv = resolve_config_dir()?;
 
             mv.push("config.toml");
-            let mut file = File::create_new(mv)?;
+            tracing::debug!(from = %current_path.display(), to = %mv.display(), "migrating existing config");
+            let mut file = create_private_file(&mv)?;
 
             cfg.read_to_end(&mut tmp)?;
-            file.write_all(&mut tmp)?;
+            file.write_all(&tmp)?;
+            report.moved = Some((current_path, mv));
 
-            switch_config("config")?;
+            switch_config("config", &SwitchOptions::default())?;
+            report.switched_to = Some("config".to_string());
         }
     }
-    Ok(())
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch_and_rename(from: &str, to: &str, link: LinkMode) -> (PathBuf, PathBuf) {
+        fs::write(config_path(from).unwrap(), "[build]\njobs = 2\n").unwrap();
+        let options = SwitchOptions {
+            link,
+            ..SwitchOptions::default()
+        };
+        switch_config(from, &options).unwrap();
+        rename_config(from, to, false).unwrap();
+        (resolve_live_config().unwrap(), config_path(to).unwrap())
+    }
+
+    #[test]
+    fn renaming_the_active_config_keeps_the_hard_link() {
+        let _home = test_support::cargo_home();
+        let (live, renamed) = switch_and_rename("rename-hard", "renamed-hard", LinkMode::Hard);
+
+        assert_eq!(current_config().unwrap().as_deref(), Some("renamed-hard"));
+        assert!(links::same_file(
+            &fs::metadata(&live).unwrap(),
+            &fs::metadata(&renamed).unwrap()
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failed_command_still_restores_the_previous_config() {
+        let _home = test_support::cargo_home();
+        for name in ["temporary-previous", "temporary-other"] {
+            fs::write(config_path(name).unwrap(), "[build]\njobs = 2\n").unwrap();
+        }
+        switch_config("temporary-previous", &SwitchOptions::default()).unwrap();
+
+        let command = ["sh", "-c", "exit 3"].map(String::from);
+        let status =
+            temporary_switch("temporary-other", &SwitchOptions::default(), &command).unwrap();
+
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(
+            current_config().unwrap().as_deref(),
+            Some("temporary-previous")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_composed_live_config_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _home = test_support::cargo_home();
+        fs::write(
+            config_path("private-base").unwrap(),
+            "[net]\noffline = true\n",
+        )
+        .unwrap();
+        fs::write(
+            config_path("private-child").unwrap(),
+            "extends = \"private-base\"\n",
+        )
+        .unwrap();
+        switch_config("private-child", &SwitchOptions::default()).unwrap();
+
+        let live = fs::metadata(resolve_live_config().unwrap()).unwrap();
+        assert_eq!(live.permissions().mode() & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn renaming_the_active_config_repoints_the_symlink() {
+        let _home = test_support::cargo_home();
+        let (live, renamed) =
+            switch_and_rename("rename-symlink", "renamed-symlink", LinkMode::Symlink);
+
+        assert_eq!(
+            current_config().unwrap().as_deref(),
+            Some("renamed-symlink")
+        );
+        assert!(fs::symlink_metadata(&live).unwrap().is_symlink());
+        assert_eq!(
+            fs::canonicalize(&live).unwrap(),
+            fs::canonicalize(&renamed).unwrap()
+        );
+    }
 }
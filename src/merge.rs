@@ -0,0 +1,16 @@
+use toml::{Table, Value};
+
+/// Merge `overlay` into `base` the way cargo merges config files: tables are
+/// merged key by key, arrays are concatenated and any other value in
+/// `overlay` replaces the one in `base`.
+pub fn deep_merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => deep_merge(base, overlay),
+            (Some(Value::Array(base)), Value::Array(overlay)) => base.extend(overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
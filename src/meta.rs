@@ -0,0 +1,137 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs, io,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use crate::resolve_config_dir;
+
+/// Extra information about a config, kept out of the config itself so cargo
+/// never sees it.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, Clone, PartialEq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ConfigMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Hidden from `list` and completion, and only switched to with `--force`.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// Only used through another config's `include`, never switched to.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fragment: bool,
+    /// Rendered with this machine's values on switch, written out instead of
+    /// linked.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub template: bool,
+    /// Free-form labels for `list --tag`, such as `work` or `offline`.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// Where an imported config was exported from, read from an `export
+    /// --annotate` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// The origin of an exported config.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// Host name of the exporting machine.
+    pub host: String,
+    /// Name of the config there.
+    pub name: String,
+    /// When it was exported, as RFC 3339.
+    pub exported: String,
+}
+
+/// This machine's host name, or `unknown`.
+pub fn hostname() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .map(|host| host.trim().to_string())
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl Provenance {
+    /// The provenance of `name` exported from this machine now.
+    pub fn here(name: &str) -> Provenance {
+        Provenance {
+            host: hostname(),
+            name: name.to_string(),
+            exported: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        }
+    }
+}
+
+impl ConfigMeta {
+    pub fn is_empty(&self) -> bool {
+        *self == ConfigMeta::default()
+    }
+}
+
+/// Metadata for every config, keyed by name.
+pub type MetaStore = BTreeMap<String, ConfigMeta>;
+
+fn meta_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-meta");
+    Ok(path)
+}
+
+pub fn load() -> io::Result<MetaStore> {
+    let content = match fs::read_to_string(meta_path()?) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(MetaStore::new()),
+        Err(err) => return Err(err),
+    };
+
+    toml::from_str(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cargo-config-meta is not valid: {err}"),
+        )
+    })
+}
+
+pub fn save(store: &MetaStore) -> io::Result<()> {
+    let content = toml::to_string(store).map_err(io::Error::other)?;
+    fs::write(meta_path()?, content)
+}
+
+pub fn get(name: &str) -> io::Result<ConfigMeta> {
+    Ok(load()?.remove(name).unwrap_or_default())
+}
+
+pub fn set(name: &str, meta: ConfigMeta) -> io::Result<()> {
+    let mut store = load()?;
+    if meta.is_empty() {
+        store.remove(name);
+    } else {
+        store.insert(name.to_string(), meta);
+    }
+    save(&store)
+}
+
+/// Drop a config's metadata, if it has any.
+pub fn remove(name: &str) -> io::Result<()> {
+    let mut store = load()?;
+    if store.remove(name).is_some() {
+        save(&store)?;
+    }
+    Ok(())
+}
+
+/// Move a config's metadata to its new name.
+pub fn rename(from: &str, to: &str) -> io::Result<()> {
+    let mut store = load()?;
+    if let Some(meta) = store.remove(from) {
+        store.insert(to.to_string(), meta);
+        save(&store)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use owo_colors::OwoColorize;
+use std::{fs, io};
+use toml_edit::{DocumentMut, Item, Table, TableLike};
+
+use crate::{
+    batch, config_names, existing_config_path, format::Format, global, resolve_cargo_dir,
+    switch_config, transfer, warning, SwitchOptions,
+};
+
+/// What to do with a deprecated key.
+enum Action {
+    /// Drop the key, cargo no longer reads it.
+    Remove(&'static str),
+    /// Replace the key with another key set to a fixed value.
+    Replace(&'static str, &'static str),
+    /// Leave the key alone but point out what to do instead, for keys that
+    /// cannot be rewritten mechanically.
+    Warn(&'static str),
+}
+
+/// Deprecated cargo config keys and how to bring them up to date.
+const MIGRATIONS: &[(&str, Action)] = &[
+    (
+        "unstable.sparse-registry",
+        Action::Replace("registries.crates-io.protocol", "sparse"),
+    ),
+    (
+        "build.pipelining",
+        Action::Remove("pipelining is always enabled"),
+    ),
+    (
+        "cargo-new.name",
+        Action::Remove("cargo new no longer fills in authors"),
+    ),
+    (
+        "cargo-new.email",
+        Action::Remove("cargo new no longer fills in authors"),
+    ),
+    (
+        "registry.index",
+        Action::Warn("use [registries] with [source] replacement instead"),
+    ),
+];
+
+pub fn migrate_configs(name: Option<&str>, all: bool) -> io::Result<()> {
+    let names = match (name, all) {
+        (_, true) => config_names()?,
+        (Some(name), false) => vec![name.to_string()],
+        (None, false) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Pass a config name or --all",
+            ))
+        }
+    };
+
+    let mut failed = vec![];
+    for name in &names {
+        match migrate_config(name) {
+            Ok(()) => {}
+            Err(err) if global().keep_going => {
+                batch::report_failure(&err);
+                failed.push(name.clone());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    batch::summary(&failed, names.len())
+}
+
+/// Move a legacy extensionless `~/.cargo/config`, which older cargo read, into
+/// the store as `name` and switch to it. The original is kept as
+/// `config.bak` in the cargo dir.
+pub fn migrate_legacy_config(name: &str) -> io::Result<()> {
+    let mut legacy = resolve_cargo_dir()?;
+    legacy.push("config");
+    if !legacy.is_file() {
+        warning::print(format!(
+            "No legacy config at {}, nothing to migrate",
+            legacy.display()
+        ));
+        return Ok(());
+    }
+
+    transfer::import_config(&legacy, Some(name), Some(Format::Toml), true, false)?;
+
+    let backup = legacy.with_extension("bak");
+    tracing::debug!(from = %legacy.display(), to = %backup.display(), "backing up legacy config");
+    fs::copy(&legacy, &backup)?;
+    fs::remove_file(&legacy)?;
+
+    switch_config(name, &SwitchOptions::default())?;
+    println!(
+        "Success:   {}  Migrated {} to {name} and switched to it, the original is at {}",
+        "✓".green(),
+        legacy.display(),
+        backup.display()
+    );
+    Ok(())
+}
+
+/// Rewrite the deprecated keys in one config, keeping a `.bak` copy of the
+/// original if anything changed.
+fn migrate_config(name: &str) -> io::Result<()> {
+    let path = existing_config_path(name)?;
+    let original = fs::read_to_string(&path)?;
+    let mut doc = original.parse::<DocumentMut>().map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{name} is not valid TOML: {err}"),
+        )
+    })?;
+
+    let mut changed = false;
+    for (key, action) in MIGRATIONS {
+        if get(doc.as_table(), key).is_none() {
+            continue;
+        }
+
+        match action {
+            Action::Remove(reason) => {
+                changed = true;
+                remove(doc.as_table_mut(), key);
+                println!(
+                    "Changed:   {}  {name}: removed {key} ({reason})",
+                    "↻".cyan()
+                );
+            }
+            Action::Replace(new_key, value) => {
+                changed = true;
+                let old = remove(doc.as_table_mut(), key);
+                // Only a switched-on flag maps onto the new setting.
+                if old.and_then(|old| old.as_bool()) != Some(false) {
+                    insert(doc.as_table_mut(), new_key, toml_edit::value(*value));
+                }
+                println!(
+                    "Changed:   {}  {name}: replaced {key} with {new_key} = \"{value}\"",
+                    "↻".cyan()
+                );
+            }
+            Action::Warn(advice) => {
+                warning::print(format!("{name}: {key} is deprecated, {advice}"));
+            }
+        }
+    }
+
+    if !changed {
+        println!("Success:   {}  {name} is up to date", "✓".green());
+        return Ok(());
+    }
+
+    let mut backup = path.clone();
+    backup.set_extension("toml.bak");
+    fs::write(&backup, &original)?;
+    // Written in place so the hard link to an active config stays intact.
+    fs::write(&path, doc.to_string())?;
+
+    println!(
+        "Success:   {}  Migrated {name}, the original is at {}",
+        "✓".green(),
+        backup.display()
+    );
+    Ok(())
+}
+
+/// The item at a dotted `key`, if there is one.
+fn get<'a>(table: &'a dyn TableLike, key: &str) -> Option<&'a Item> {
+    match key.split_once('.') {
+        None => table.get(key),
+        Some((head, rest)) => get(table.get(head)?.as_table_like()?, rest),
+    }
+}
+
+/// Remove the item at a dotted `key`, dropping tables it leaves empty.
+fn remove(table: &mut dyn TableLike, key: &str) -> Option<Item> {
+    match key.split_once('.') {
+        None => table.remove(key),
+        Some((head, rest)) => {
+            let child = table.get_mut(head)?.as_table_like_mut()?;
+            let removed = remove(child, rest);
+            if child.is_empty() {
+                table.remove(head);
+            }
+            removed
+        }
+    }
+}
+
+/// Insert `item` at a dotted `key`, creating the tables on the way.
+fn insert(table: &mut dyn TableLike, key: &str, item: Item) {
+    match key.split_once('.') {
+        None => {
+            table.insert(key, item);
+        }
+        Some((head, rest)) => {
+            let child = table.entry(head).or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            });
+            if let Some(child) = child.as_table_like_mut() {
+                insert(child, rest, item);
+            }
+        }
+    }
+}
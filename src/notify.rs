@@ -0,0 +1,42 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use crate::warning;
+
+/// How long to wait for a FIFO to be opened by its reader.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+fn write_name(path: &Path, name: &str) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?;
+    writeln!(file, "{name}")
+}
+
+/// Append the newly active config `name` to `path` as a line, for a watching
+/// process such as an editor integration. `path` may be a file or a FIFO;
+/// opening a FIFO nobody reads blocks, so this gives up after a moment.
+/// Failures only warn, the switch has already happened.
+pub fn notify(path: &Path, name: &str) {
+    let (sender, receiver) = mpsc::channel();
+    let (target, line) = (PathBuf::from(path), name.to_string());
+    // Left running on timeout, it ends with the process.
+    thread::spawn(move || {
+        let _ = sender.send(write_name(&target, &line));
+    });
+
+    tracing::debug!(path = %path.display(), name, "notifying of switch");
+    let err = match receiver.recv_timeout(TIMEOUT) {
+        Ok(Ok(())) => return,
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => "nothing is reading it".to_string(),
+    };
+    warning::print(format!("Could not notify {}: {err}", path.display()));
+}
@@ -0,0 +1,136 @@
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::PathBuf,
+};
+
+use crate::{current_config, global, links, resolve_live_config};
+
+/// What the live config is, as far as a command that must not touch it
+/// could change it.
+enum Live {
+    Missing,
+    Symlink(PathBuf),
+    File(Metadata, Vec<u8>),
+}
+
+/// The active state: the config `cargo-config-current` names and the live
+/// config.
+pub struct ActiveState {
+    current: Option<String>,
+    live: Live,
+}
+
+fn live() -> io::Result<Live> {
+    let path = resolve_live_config()?;
+    let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Live::Missing),
+        Err(err) => return Err(err),
+    };
+    if metadata.is_symlink() {
+        return Ok(Live::Symlink(fs::read_link(&path)?));
+    }
+    let content = fs::read(&path)?;
+    Ok(Live::File(metadata, content))
+}
+
+fn snapshot() -> io::Result<ActiveState> {
+    Ok(ActiveState {
+        current: current_config()?,
+        live: live()?,
+    })
+}
+
+/// Record the active state under `--paranoid`, to compare against with
+/// [`check_unchanged`]. Without it nothing is read.
+pub fn capture() -> io::Result<Option<ActiveState>> {
+    if !global().paranoid {
+        return Ok(None);
+    }
+    snapshot().map(Some)
+}
+
+/// Error if the active state is no longer what [`capture`] recorded before
+/// `operation`, which should have left it alone.
+pub fn check_unchanged(before: Option<ActiveState>, operation: &str) -> io::Result<()> {
+    let Some(before) = before else {
+        return Ok(());
+    };
+
+    let current = current_config()?;
+    let path = resolve_live_config()?;
+    let changed = if current != before.current {
+        Some(format!(
+            "the active config went from {} to {}",
+            before.current.as_deref().unwrap_or("none"),
+            current.as_deref().unwrap_or("none")
+        ))
+    } else {
+        let unchanged = match (&before.live, live()?) {
+            (Live::Missing, Live::Missing) => true,
+            (Live::Symlink(before), Live::Symlink(after)) => *before == after,
+            (Live::File(before, content), Live::File(after, new_content)) => {
+                // Without inodes only the content can be compared.
+                (cfg!(not(unix)) || links::same_file(before, &after)) && *content == new_content
+            }
+            _ => false,
+        };
+        (!unchanged).then(|| format!("{} changed", path.display()))
+    };
+
+    tracing::debug!(
+        operation,
+        changed = changed.is_some(),
+        "checked active state"
+    );
+    match changed {
+        Some(changed) => Err(io::Error::other(format!(
+            "{operation} should not touch the active config, but {changed}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config_path, create_config, switch_config, test_support, Cli, Config, CreateArgs,
+        SwitchOptions,
+    };
+    use clap::Parser;
+
+    fn create_args(name: &str) -> CreateArgs {
+        match Cli::parse_from(["cargo-config-profiles", "create", name]).command {
+            Config::Create(args) => args,
+            command => unreachable!("parsed {command:?}"),
+        }
+    }
+
+    fn activate(name: &str) {
+        fs::write(config_path(name).unwrap(), "[build]\njobs = 2\n").unwrap();
+        switch_config(name, &SwitchOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn creating_a_config_leaves_the_active_state_alone() {
+        let _home = test_support::cargo_home();
+        activate("paranoid-active");
+
+        let before = snapshot().unwrap();
+        create_config(&create_args("paranoid-new"), "paranoid-new", None).unwrap();
+        check_unchanged(Some(before), "create").unwrap();
+    }
+
+    #[test]
+    fn a_changed_live_config_is_caught() {
+        let _home = test_support::cargo_home();
+        activate("paranoid-changed");
+
+        let before = snapshot().unwrap();
+        let live = resolve_live_config().unwrap();
+        fs::write(&live, "[build]\njobs = 3\n").unwrap();
+        assert!(check_unchanged(Some(before), "create").is_err());
+    }
+}
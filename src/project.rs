@@ -0,0 +1,188 @@
+use owo_colors::OwoColorize;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    existing_config_path, link_config, links, permission_hint, resolve_config_dir, warning,
+    LinkMode,
+};
+
+/// Project config files linked to stored configs, keyed by path. These are
+/// separate from the active config, which only covers the cargo home.
+pub type LinkStore = BTreeMap<PathBuf, String>;
+
+fn links_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-links");
+    Ok(path)
+}
+
+pub fn load() -> io::Result<LinkStore> {
+    let content = match fs::read_to_string(links_path()?) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(LinkStore::new()),
+        Err(err) => return Err(err),
+    };
+
+    toml::from_str(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cargo-config-links is not valid: {err}"),
+        )
+    })
+}
+
+pub fn save(store: &LinkStore) -> io::Result<()> {
+    let content = toml::to_string(store).map_err(io::Error::other)?;
+    fs::write(links_path()?, content)
+}
+
+/// Link `name` to `into`, such as a crate's `.cargo/config.toml`, creating
+/// its parent directories, and remember the link for `links`.
+pub fn link_into(name: &str, into: &Path, mode: LinkMode) -> io::Result<PathBuf> {
+    let source = existing_config_path(name)?;
+
+    let parent = match into.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent).map_err(|err| permission_hint(err, parent))?;
+    let file_name = into.file_name().ok_or(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} is not a file path", into.display()),
+    ))?;
+    // Tracked by absolute path, so `links` means the same from anywhere.
+    let target = fs::canonicalize(parent)?.join(file_name);
+
+    if fs::symlink_metadata(&target).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+
+    tracing::debug!(name, target = %target.display(), "linking config into project");
+    link_config(&source, &target, mode).map_err(|err| permission_hint(err, &target))?;
+
+    let mut store = load()?;
+    store.insert(target.clone(), name.to_string());
+    save(&store)?;
+    Ok(target)
+}
+
+/// Print every project link, noting the ones that no longer point at their
+/// config. Links whose file is gone are dropped from the record.
+pub fn list_links() -> io::Result<()> {
+    let mut store = load()?;
+    let before = store.len();
+    store.retain(|path, _| fs::symlink_metadata(path).is_ok());
+    if store.len() != before {
+        save(&store)?;
+    }
+
+    println!("List of project links:");
+    for (path, name) in &store {
+        let intact = existing_config_path(name)
+            .and_then(|source| {
+                Ok(links::same_file(
+                    &fs::metadata(source)?,
+                    &fs::metadata(path)?,
+                ))
+            })
+            .unwrap_or(false);
+        if intact {
+            println!("- {} -> {name}", path.display());
+        } else {
+            println!("- {} -> {name} (broken)", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Relink every project link that no longer points at its config, such as
+/// after the cargo home was copied or moved, as a symlink again if it was one.
+/// Links whose file was deleted are left alone, and a file with different
+/// contents is only replaced with `force`, since its edits would be lost.
+/// Returns how many links were left broken.
+pub fn repair_links(force: bool) -> io::Result<usize> {
+    let mut left_broken = 0;
+    for (path, name) in load()? {
+        let Ok(link) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+        let source = match existing_config_path(&name) {
+            Ok(source) => source,
+            Err(err) => {
+                warning::print(format!("Left {} broken: {err}", path.display()));
+                left_broken += 1;
+                continue;
+            }
+        };
+        let stored = fs::metadata(&source)?;
+        if fs::metadata(&path).is_ok_and(|linked| links::same_file(&stored, &linked)) {
+            continue;
+        }
+
+        // A symlink into the old cargo home dangles, and has nothing to lose.
+        let content = match fs::read(&path) {
+            Ok(content) => Some(content),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        let differs = match content {
+            Some(content) => content != fs::read(&source)?,
+            None => false,
+        };
+        if differs && !force {
+            warning::print(format!(
+                "Left {} broken, it has different contents than {name}; rerun with --force to replace it",
+                path.display()
+            ));
+            left_broken += 1;
+            continue;
+        }
+
+        let mode = if link.is_symlink() {
+            LinkMode::Symlink
+        } else {
+            LinkMode::Hard
+        };
+        tracing::debug!(name, path = %path.display(), "relinking project link");
+        fs::remove_file(&path).map_err(|err| permission_hint(err, &path))?;
+        link_config(&source, &path, mode).map_err(|err| permission_hint(err, &path))?;
+        println!("Relinked:  {}  {} to {name}", "→".cyan(), path.display());
+    }
+    Ok(left_broken)
+}
+
+/// Point the project links of `from` at `to` after a rename. The linked files
+/// themselves are untouched, a rename keeps the inode.
+pub fn rename(from: &str, to: &str) -> io::Result<()> {
+    let mut store = load()?;
+    let mut changed = false;
+    for name in store.values_mut().filter(|name| *name == from) {
+        *name = to.to_string();
+        changed = true;
+    }
+    if changed {
+        save(&store)?;
+    }
+    Ok(())
+}
+
+/// Warn that removing `name` leaves the project links to it behind. Printed to
+/// stderr so `remove --json` output stays parseable.
+pub fn warn_linked(name: &str) -> io::Result<()> {
+    for (path, linked) in load()? {
+        if linked == name && fs::symlink_metadata(&path).is_ok() {
+            warning::eprint(format!(
+                "{name} is linked into {}, which is left behind",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
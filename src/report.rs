@@ -0,0 +1,104 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{env, fs, io, path::Path};
+
+use crate::{config_names, current_config, dir_lines, doctor, verify, warning};
+
+/// A diagnostic bundle for a bug report, as written by `--report-file`. It
+/// holds no config contents, only names and whether they parse, so it is
+/// safe to attach.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct Report {
+    version: &'static str,
+    os: &'static str,
+    /// The command line, with every `=VALUE` redacted.
+    command: Vec<String>,
+    /// The command's error, if it failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The lines `--print-dir` prints.
+    paths: Vec<String>,
+    doctor: Vec<doctor::Check>,
+    configs: Vec<ReportedConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active: Option<String>,
+    warnings: Vec<String>,
+    /// Parts of the report that could not be gathered.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<String>,
+}
+
+/// A stored config and whether it parses.
+#[derive(Serialize, JsonSchema)]
+pub struct ReportedConfig {
+    name: String,
+    valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Only the first line of an error, since TOML errors go on to quote the
+/// offending line, which may hold a token.
+fn first_line(err: impl ToString) -> String {
+    let err = err.to_string();
+    err.lines().next().unwrap_or_default().to_string()
+}
+
+/// Keep a flag or a name, but not what follows `=`, such as a template
+/// `--var token=...`.
+fn redact(arg: String) -> String {
+    match arg.split_once('=') {
+        Some((key, _)) => format!("{key}=<redacted>"),
+        None => arg,
+    }
+}
+
+/// The gathered part, or `None` with why it is missing noted in `missing`.
+fn gather<T>(missing: &mut Vec<String>, part: &str, result: io::Result<T>) -> Option<T> {
+    result
+        .inspect_err(|err| missing.push(format!("{part}: {}", first_line(err))))
+        .ok()
+}
+
+fn collect(error: Option<&miette::Report>) -> Report {
+    let mut missing = vec![];
+    let paths = gather(&mut missing, "paths", dir_lines().map(Vec::from)).unwrap_or_default();
+    let doctor = gather(&mut missing, "doctor", doctor::checks()).unwrap_or_default();
+    let names = gather(&mut missing, "configs", config_names()).unwrap_or_default();
+    let active = gather(&mut missing, "active", current_config()).flatten();
+
+    let configs = names
+        .into_iter()
+        .map(|name| {
+            let error = verify::parse_config(&name).err().map(first_line);
+            ReportedConfig {
+                name,
+                valid: error.is_none(),
+                error,
+            }
+        })
+        .collect();
+
+    Report {
+        version: env!("CARGO_PKG_VERSION"),
+        os: env::consts::OS,
+        command: env::args().map(redact).collect(),
+        error: error.map(first_line),
+        paths,
+        doctor,
+        configs,
+        active,
+        warnings: warning::emitted(),
+        missing,
+    }
+}
+
+/// Write a [`Report`] on the command that just ran, and its `error` if it
+/// failed, to `path` as JSON.
+pub fn write(path: &Path, error: Option<&miette::Report>) -> io::Result<()> {
+    let report = collect(error);
+    let json = serde_json::to_string_pretty(&report).map_err(io::Error::other)?;
+    tracing::debug!(path = %path.display(), "writing diagnostic report");
+    fs::write(path, json + "\n")
+}
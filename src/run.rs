@@ -0,0 +1,90 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Command, ExitStatus},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    create_private_dir, create_private_file, existing_config_path, resolve_cargo_dir, template,
+};
+
+/// A throwaway cargo home, deleted when dropped so it is cleaned up on every
+/// exit path.
+struct TempCargoHome {
+    path: PathBuf,
+}
+
+impl Drop for TempCargoHome {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.path) {
+            tracing::debug!(path = %self.path.display(), "could not clean up: {err}");
+        }
+    }
+}
+
+/// Run cargo with `name` as its config, leaving the active config alone.
+///
+/// Cargo is pointed at a temporary `CARGO_HOME` holding the config as a
+/// switch would write it, next to links to everything else in the real cargo
+/// home, so registries, credentials and installed binaries are shared as
+/// usual.
+pub fn run_with_config(name: &str, args: &[String]) -> io::Result<ExitStatus> {
+    let config = existing_config_path(name)?;
+    let cargo_dir = resolve_cargo_dir()?;
+
+    // Never reused: a directory already there may have been planted in a
+    // shared temp dir, or left by an earlier run.
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let mut path = env::temp_dir();
+    path.push(format!("cargo-config-run-{}-{nanos:09}", process::id()));
+    create_private_dir(&path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("Could not create {}: {err}", path.display()),
+        )
+    })?;
+    let home = TempCargoHome { path };
+
+    for entry in fs::read_dir(&cargo_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        if matches!(
+            file_name.to_str(),
+            Some("config.toml" | "config" | "cargo-config")
+        ) {
+            continue;
+        }
+        link(&entry.path(), &home.path.join(file_name))?;
+    }
+    let live = home.path.join("config.toml");
+    match template::live_content(name)? {
+        Some(content) => create_private_file(&live)?.write_all(content.as_bytes())?,
+        None => drop(fs::copy(config, live)?),
+    }
+
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    tracing::debug!(cargo_home = %home.path.display(), ?args, "running cargo");
+    Command::new(cargo)
+        .args(args)
+        .env("CARGO_HOME", &home.path)
+        .status()
+}
+
+#[cfg(unix)]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(original, link)
+}
+
+#[cfg(windows)]
+fn link(original: &Path, link: &Path) -> io::Result<()> {
+    if original.is_dir() {
+        std::os::windows::fs::symlink_dir(original, link)
+    } else {
+        std::os::windows::fs::symlink_file(original, link)
+    }
+}
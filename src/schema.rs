@@ -0,0 +1,77 @@
+use clap::ValueEnum;
+use schemars::{schema_for, Schema};
+use std::{collections::BTreeMap, io};
+
+use crate::{
+    batch::Outcome,
+    diff::{Change, KeyChange},
+    doctor::Check,
+    list::JsonEntry,
+    meta::MetaStore,
+    report::Report,
+    settings::Settings,
+    state::Dump,
+    stats::Stats,
+};
+
+/// A file or output whose shape `json-schema` can describe.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Document {
+    /// `cargo-config-settings`
+    Settings,
+    /// `cargo-config-meta`, the per-config metadata sidecar
+    Meta,
+    /// `dump` output and `restore` input
+    Dump,
+    /// The `--json` summary of `create` and `remove`
+    Batch,
+    /// `diff --json` change records
+    Diff,
+    /// `diff --base --json` key changes
+    ThreeWay,
+    /// `stats --json` output
+    Stats,
+    /// `doctor --json` checks
+    Doctor,
+    /// `list --json` entries
+    List,
+    /// The `--report-file` diagnostic bundle
+    Report,
+}
+
+impl Document {
+    fn schema(self) -> Schema {
+        match self {
+            Document::Settings => schema_for!(Settings),
+            Document::Meta => schema_for!(MetaStore),
+            Document::Dump => schema_for!(Dump),
+            Document::Batch => schema_for!(Vec<Outcome>),
+            Document::Diff => schema_for!(Vec<Change>),
+            Document::ThreeWay => schema_for!(Vec<KeyChange>),
+            Document::Stats => schema_for!(Stats),
+            Document::Doctor => schema_for!(Vec<Check>),
+            Document::List => schema_for!(Vec<JsonEntry>),
+            Document::Report => schema_for!(Report),
+        }
+    }
+}
+
+/// Print the JSON Schema for `document`, or an object of every schema keyed by
+/// name.
+pub fn print_schema(document: Option<Document>) -> io::Result<()> {
+    let json = match document {
+        Some(document) => serde_json::to_string_pretty(&document.schema()),
+        None => {
+            let schemas = Document::value_variants()
+                .iter()
+                .filter_map(|document| {
+                    let name = document.to_possible_value()?.get_name().to_string();
+                    Some((name, document.schema()))
+                })
+                .collect::<BTreeMap<_, _>>();
+            serde_json::to_string_pretty(&schemas)
+        }
+    };
+    println!("{}", json.map_err(io::Error::other)?);
+    Ok(())
+}
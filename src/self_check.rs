@@ -0,0 +1,161 @@
+use owo_colors::OwoColorize;
+use std::{collections::BTreeSet, io, path::PathBuf};
+
+use crate::{
+    config_names, current_config, history, meta, project, set_current_config, trash, warning,
+};
+
+/// A way the bookkeeping files disagree with the store, and how `--fix`
+/// prunes it.
+enum Problem {
+    /// `cargo-config-current` names a config that does not exist.
+    MissingCurrent(String),
+    /// `cargo-config-previous` names a config that is neither stored nor
+    /// trashed.
+    MissingPrevious(String),
+    /// Metadata kept for a config that is neither stored nor trashed.
+    OrphanMeta(String),
+    /// A project link recorded for a config that is neither stored nor
+    /// trashed.
+    OrphanLink(String, PathBuf),
+    /// A switch time kept for a config that is neither stored nor trashed.
+    OrphanLastActive(String),
+}
+
+impl Problem {
+    fn describe(&self) -> String {
+        match self {
+            Problem::MissingCurrent(name) => {
+                format!("cargo-config-current names {name}, which does not exist")
+            }
+            Problem::MissingPrevious(name) => format!(
+                "cargo-config-previous names {name}, which is neither stored nor in the trash"
+            ),
+            Problem::OrphanMeta(name) => {
+                format!("cargo-config-meta has metadata for {name}, which does not exist")
+            }
+            Problem::OrphanLink(name, path) => format!(
+                "cargo-config-links records {} as linked to {name}, which does not exist",
+                path.display()
+            ),
+            Problem::OrphanLastActive(name) => format!(
+                "cargo-config-last-active records when {name} was active, which does not exist"
+            ),
+        }
+    }
+}
+
+/// Every problem found, comparing the bookkeeping against the stored and
+/// trashed configs. Trashed configs still count, since restoring one brings
+/// its metadata and links back into use.
+fn find_problems() -> io::Result<Vec<Problem>> {
+    let stored = config_names()?.into_iter().collect::<BTreeSet<_>>();
+    let known = trash::entries()?
+        .into_iter()
+        .map(|entry| entry.name)
+        .chain(stored.iter().cloned())
+        .collect::<BTreeSet<_>>();
+
+    let mut problems = vec![];
+    if let Some(current) = current_config()?.filter(|name| !stored.contains(name)) {
+        problems.push(Problem::MissingCurrent(current));
+    }
+    if let Some(previous) = history::previous()?.filter(|name| !known.contains(name)) {
+        problems.push(Problem::MissingPrevious(previous));
+    }
+    for name in meta::load()?.into_keys() {
+        if !known.contains(&name) {
+            problems.push(Problem::OrphanMeta(name));
+        }
+    }
+    for (path, name) in project::load()? {
+        if !known.contains(&name) {
+            problems.push(Problem::OrphanLink(name, path));
+        }
+    }
+    for name in history::last_active()?.into_keys() {
+        if !known.contains(&name) {
+            problems.push(Problem::OrphanLastActive(name));
+        }
+    }
+    Ok(problems)
+}
+
+/// Check that the bookkeeping files agree with the store, and with `fix`
+/// prune every record that does not. The configs themselves are never
+/// touched.
+pub fn self_check(fix: bool) -> io::Result<()> {
+    let problems = find_problems()?;
+    if problems.is_empty() {
+        println!("Success:   {}  Bookkeeping is consistent", "✓".green());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        warning::print(problem.describe());
+    }
+    if !fix {
+        return Ok(());
+    }
+
+    let mut metas = meta::load()?;
+    let mut links = project::load()?;
+    let mut last_active = history::last_active()?;
+    for problem in &problems {
+        match problem {
+            Problem::MissingCurrent(_) => set_current_config("")?,
+            Problem::MissingPrevious(_) => history::clear_previous()?,
+            Problem::OrphanMeta(name) => drop(metas.remove(name)),
+            Problem::OrphanLink(_, path) => drop(links.remove(path)),
+            Problem::OrphanLastActive(name) => drop(last_active.remove(name)),
+        }
+    }
+    // Only rewritten when something was pruned, so a missing file stays
+    // missing.
+    if problems.iter().any(|p| matches!(p, Problem::OrphanMeta(_))) {
+        meta::save(&metas)?;
+    }
+    if problems
+        .iter()
+        .any(|p| matches!(p, Problem::OrphanLink(..)))
+    {
+        project::save(&links)?;
+    }
+    if problems
+        .iter()
+        .any(|p| matches!(p, Problem::OrphanLastActive(_)))
+    {
+        history::save_last_active(&last_active)?;
+    }
+
+    println!(
+        "Success:   {}  Pruned {} inconsistent record(s)",
+        "✓".green(),
+        problems.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn a_switch_time_for_a_missing_config_is_found_and_pruned() {
+        let _home = test_support::cargo_home();
+        history::record_left("self-check-gone").unwrap();
+        let orphaned = |problems: Vec<Problem>| {
+            problems
+                .iter()
+                .any(|p| matches!(p, Problem::OrphanLastActive(name) if name == "self-check-gone"))
+        };
+        assert!(orphaned(find_problems().unwrap()));
+
+        self_check(true).unwrap();
+        assert!(!orphaned(find_problems().unwrap()));
+        assert!(!history::last_active()
+            .unwrap()
+            .contains_key("self-check-gone"));
+    }
+}
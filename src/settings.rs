@@ -0,0 +1,65 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+use crate::resolve_config_dir;
+
+/// User preferences, read from `cargo-config-settings` in the config
+/// directory. Every key is optional.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Settings {
+    /// Make `remove` move configs to the trash unless `--no-trash` is given.
+    pub trash_on_remove: bool,
+    /// Reject new config names that do not match `name-pattern`, as if
+    /// `--strict-names` were always given.
+    pub strict_names: bool,
+    /// Allow new config names that clash with the tool's own files, or with
+    /// an existing config by case alone, as if `--allow-reserved` were always
+    /// given.
+    pub allow_reserved: bool,
+    /// Regex new config names must match under `--strict-names`, defaulting
+    /// to [`DEFAULT_NAME_PATTERN`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_pattern: Option<String>,
+    /// Script run before every switch, instead of `hooks/pre-switch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_switch_hook: Option<PathBuf>,
+    /// Script run after every switch, instead of `hooks/post-switch`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_switch_hook: Option<PathBuf>,
+    /// Warn when the live config changed outside the tool, as if
+    /// `--watch-cargo-home` were always given.
+    pub watch_cargo_home: bool,
+    /// Ask before deleting or replacing a config, as if
+    /// `--confirm-destructive` were always given.
+    pub confirm_destructive: bool,
+}
+
+pub const DEFAULT_NAME_PATTERN: &str = "^[a-z0-9-]+(/[a-z0-9-]+)*$";
+
+pub fn settings_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-settings");
+    Ok(path)
+}
+
+pub fn load() -> io::Result<Settings> {
+    let content = match fs::read_to_string(settings_path()?) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(err) => return Err(err),
+    };
+
+    toml::from_str(&content).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("cargo-config-settings is not valid: {err}"),
+        )
+    })
+}
+
+pub fn save(settings: &Settings) -> io::Result<()> {
+    let content = toml::to_string(settings).map_err(io::Error::other)?;
+    fs::write(settings_path()?, content)
+}
@@ -0,0 +1,134 @@
+use owo_colors::OwoColorize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use crate::{
+    check_new_name, config_path, create_private_file, resolve_config_dir, resolve_live_config,
+    warning,
+};
+
+fn snapshot_dir() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push(".snapshots");
+    Ok(path)
+}
+
+fn snapshot_path(label: &str) -> io::Result<PathBuf> {
+    if label.is_empty() || label.contains(['/', '\\']) || label.starts_with('.') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{label:?} is not a valid snapshot label"),
+        ));
+    }
+    let mut path = snapshot_dir()?;
+    path.push(format!("{label}.toml"));
+    Ok(path)
+}
+
+/// Copy the live config into `.snapshots/` as `label`, or the current time
+/// if `label` is empty. A copy rather than a link, so later edits to the
+/// config do not reach the snapshot. Only warns if there is no live config.
+pub fn take(label: &str) -> io::Result<()> {
+    let live = resolve_live_config()?;
+    let content = match fs::read(&live) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            warning::print(format!(
+                "{} does not exist, nothing to snapshot",
+                live.display()
+            ));
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+
+    // Colons are not allowed in Windows file names.
+    let label = match label {
+        "" => humantime::format_rfc3339_seconds(SystemTime::now())
+            .to_string()
+            .replace(':', "-"),
+        label => label.to_string(),
+    };
+    let path = snapshot_path(&label)?;
+    fs::create_dir_all(snapshot_dir()?)?;
+    let mut file = create_private_file(&path).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("A snapshot labelled {label} already exists"),
+        ),
+        _ => err,
+    })?;
+
+    tracing::debug!(from = %live.display(), to = %path.display(), "taking snapshot");
+    file.write_all(&content)?;
+    println!(
+        "Success:   {}  Saved {} as snapshot {label}",
+        "✓".green(),
+        live.display()
+    );
+    Ok(())
+}
+
+/// Print every snapshot label, oldest first.
+pub fn list_snapshots() -> io::Result<()> {
+    let read = match fs::read_dir(snapshot_dir()?) {
+        Ok(read) => read,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            println!("No snapshots");
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+
+    let mut snapshots = read
+        .flatten()
+        .filter_map(|entry| {
+            let label = entry
+                .file_name()
+                .to_str()?
+                .strip_suffix(".toml")?
+                .to_string();
+            let taken = entry.metadata().and_then(|m| m.modified()).ok()?;
+            Some((taken, label))
+        })
+        .collect::<Vec<_>>();
+    snapshots.sort();
+
+    println!("Snapshots:");
+    for (taken, label) in snapshots {
+        println!(
+            "- {label} (taken {})",
+            humantime::format_rfc3339_seconds(taken)
+        );
+    }
+    Ok(())
+}
+
+/// Store the snapshot `label` as a new config `name`, defaulting to the
+/// label, so it can be switched to.
+pub fn restore_snapshot(label: &str, name: Option<&str>) -> io::Result<String> {
+    let source = snapshot_path(label)?;
+    let content = fs::read(&source).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("There is no snapshot labelled {label}"),
+        )
+    })?;
+
+    let name = name.unwrap_or(label);
+    check_new_name(name)?;
+    let mut file = create_private_file(&config_path(name)?).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{name} already exists, pass --name to restore under another name"),
+        ),
+        _ => err,
+    })?;
+    tracing::debug!(from = %source.display(), name, "restoring snapshot");
+    file.write_all(&content)?;
+    Ok(name.to_string())
+}
@@ -0,0 +1,260 @@
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    check_new_name, config_names, config_path, create_groups, create_private_file, current_config,
+    links,
+    meta::{self, ConfigMeta},
+    parse_name, permission_hint, require_confirmation, resolve_config_dir, resolve_live_config,
+    set_current_config,
+    settings::{self, Settings},
+    switch_config, warning, SwitchOptions,
+};
+
+/// Bookkeeping files `reset` clears. Configs, their metadata, settings and
+/// project links are the user's own and are kept.
+const STATE_FILES: &[&str] = &[
+    "cargo-config-current",
+    "cargo-config-previous",
+    "cargo-config-live-hash",
+    "cargo-config-last-active",
+];
+
+/// Everything the tool tracks, as written by `dump` and read by `restore`.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
+#[serde(default)]
+pub struct Dump {
+    pub active: Option<String>,
+    pub settings: Settings,
+    pub configs: BTreeMap<String, DumpedConfig>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
+#[serde(default)]
+pub struct DumpedConfig {
+    #[serde(flatten)]
+    pub meta: ConfigMeta,
+    /// The config itself, only present when dumped with `--bundle`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+pub fn dump_state(json: bool, bundle: bool, output: Option<&Path>) -> io::Result<()> {
+    let mut metas = meta::load()?;
+    let mut configs = BTreeMap::new();
+
+    for name in config_names()? {
+        let content = bundle
+            .then(|| fs::read_to_string(config_path(&name)?))
+            .transpose()?;
+        let meta = metas.remove(&name).unwrap_or_default();
+        configs.insert(name, DumpedConfig { meta, content });
+    }
+
+    let dump = Dump {
+        active: current_config()?,
+        settings: settings::load()?,
+        configs,
+    };
+
+    let mut content = if json {
+        serde_json::to_string_pretty(&dump).map_err(io::Error::other)?
+    } else {
+        toml::to_string(&dump).map_err(io::Error::other)?
+    };
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+
+    match output {
+        Some(path) => fs::write(path, content),
+        None => io::stdout().write_all(content.as_bytes()),
+    }
+}
+
+/// Recreate the state described by a dump. Bundled configs that do not exist
+/// yet are written out, existing ones are left alone unless `force` is set.
+pub fn restore_state(path: &Path, force: bool) -> io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let dump: Dump = if content.trim_start().starts_with('{') {
+        serde_json::from_str(&content).map_err(|err| invalid_dump(path, err))?
+    } else {
+        toml::from_str(&content).map_err(|err| invalid_dump(path, err))?
+    };
+
+    // Check every name before writing anything, so a crafted dump cannot
+    // reach outside the store and a bad one restores nothing.
+    let configs = dump
+        .configs
+        .into_iter()
+        .map(|(name, config)| Ok((dumped_name(path, &name)?, config)))
+        .collect::<io::Result<Vec<_>>>()?;
+    let active = dump
+        .active
+        .map(|active| dumped_name(path, &active))
+        .transpose()?;
+
+    let mut metas = meta::load()?;
+    for (name, config) in configs {
+        let target = config_path(&name)?;
+
+        match config.content {
+            Some(content) if force || !target.exists() => {
+                tracing::debug!(path = %target.display(), "restoring bundled config");
+                if let Some((group, _)) = name.rsplit_once('/') {
+                    create_groups(group, true)?;
+                }
+                // Truncated in place, so links to an existing config see it.
+                let mut file = match target.exists() {
+                    true => OpenOptions::new().write(true).truncate(true).open(&target),
+                    false => create_private_file(&target),
+                }
+                .map_err(|err| permission_hint(err, &target))?;
+                file.write_all(content.as_bytes())?;
+                println!("Success:   {}  Restored {name}", "✓".green());
+            }
+            Some(_) => warning::print(format!(
+                "{name} already exists, keeping it (pass --force to overwrite)"
+            )),
+            None if !target.exists() => warning::print(format!(
+                "{name} is not bundled in the dump and does not exist here"
+            )),
+            None => {}
+        }
+
+        if config.meta.is_empty() {
+            metas.remove(&name);
+        } else {
+            metas.insert(name, config.meta);
+        }
+    }
+    meta::save(&metas)?;
+    settings::save(&dump.settings)?;
+
+    if let Some(active) = active {
+        if config_path(&active)?.is_file() {
+            switch_config(&active, &SwitchOptions::default())?;
+            println!("Success:   {}  Switched to {active}", "✓".green());
+        } else {
+            warning::print(format!(
+                "Active config {active} does not exist, not switching"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// `name` from the dump at `path`, checked as if it were given on the
+/// command line.
+fn dumped_name(path: &Path, name: &str) -> io::Result<String> {
+    let name = parse_name(name).map_err(|err| invalid_dump(path, err))?;
+    check_new_name(&name)?;
+    Ok(name)
+}
+
+fn invalid_dump(path: &Path, err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is not a valid dump: {err}", path.display()),
+    )
+}
+
+/// Clear the bookkeeping in [`STATE_FILES`] after confirming. With `infer`,
+/// the config the live `config.toml` is linked to, or failing that has the
+/// same content as, is recorded as active again.
+pub fn reset(infer: bool) -> io::Result<()> {
+    require_confirmation("clear the active and previous config records")?;
+
+    let dir = resolve_config_dir()?;
+    let mut cleared = vec![];
+    for file in STATE_FILES {
+        let path = dir.join(file);
+        // Emptied rather than removed: a missing record makes the next run
+        // take the live config over as a new config.
+        let result = match *file {
+            "cargo-config-current" => match current_config()? {
+                Some(_) => set_current_config(""),
+                None => continue,
+            },
+            _ => fs::remove_file(path),
+        };
+        match result {
+            Ok(()) => cleared.push(*file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    tracing::debug!(?cleared, "reset state");
+    match cleared.is_empty() {
+        true => println!("Success:   {}  There was no state to clear", "✓".green()),
+        false => println!("Success:   {}  Cleared {}", "✓".green(), cleared.join(", ")),
+    }
+
+    if !infer {
+        return Ok(());
+    }
+    let live = resolve_live_config()?;
+    let Ok(live_metadata) = fs::metadata(&live) else {
+        warning::print(format!(
+            "{} does not exist, no config is active",
+            live.display()
+        ));
+        return Ok(());
+    };
+    let names = config_names()?;
+    let linked = names.iter().find(|name| {
+        config_path(name)
+            .and_then(fs::metadata)
+            .is_ok_and(|metadata| links::same_file(&metadata, &live_metadata))
+    });
+    let inferred = match linked {
+        Some(name) => Some(name),
+        None => {
+            let content = fs::read(&live)?;
+            names.iter().find(|name| {
+                config_path(name)
+                    .and_then(fs::read)
+                    .is_ok_and(|stored| stored == content)
+            })
+        }
+    };
+
+    match inferred {
+        Some(name) => {
+            set_current_config(name)?;
+            println!("Success:   {}  Recorded {name} as active", "✓".green());
+        }
+        None => warning::print(format!(
+            "{} matches no stored config, no config is active",
+            live.display()
+        )),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn a_dumped_name_cannot_leave_the_store() {
+        let (_guard, home) = test_support::cargo_home();
+        let dump = home.join("escaping-dump.toml");
+        fs::write(&dump, "[configs.\"../escaped\"]\ncontent = \"\"\n").unwrap();
+
+        assert!(restore_state(&dump, false).is_err());
+        assert!(!resolve_config_dir()
+            .unwrap()
+            .join("../escaped.toml")
+            .exists());
+    }
+}
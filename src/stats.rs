@@ -0,0 +1,118 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::{io, time::SystemTime};
+
+use crate::list::{self, ListEntry};
+
+/// A config and its size in bytes.
+#[derive(Serialize, JsonSchema)]
+pub struct ConfigSize {
+    name: String,
+    size: u64,
+}
+
+/// Aggregate figures about the store, as printed by `stats --json`.
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct Stats {
+    configs: usize,
+    total_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    largest: Option<ConfigSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smallest: Option<ConfigSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    newest: Option<Modified>,
+    described: usize,
+    disabled: usize,
+    fragments: usize,
+    tagged: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    active: Option<String>,
+}
+
+/// A config and when it was last modified, as RFC 3339.
+#[derive(Serialize, JsonSchema)]
+pub struct Modified {
+    name: String,
+    modified: String,
+}
+
+fn sized(entry: &ListEntry) -> ConfigSize {
+    ConfigSize {
+        name: entry.name.clone(),
+        size: entry.size,
+    }
+}
+
+fn timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+/// Work out [`Stats`] from a single listing of the store, disabled configs
+/// included.
+fn collect() -> io::Result<Stats> {
+    let entries = list::entries(true)?;
+    let count = |state: fn(&ListEntry) -> bool| entries.iter().filter(|e| state(e)).count();
+
+    Ok(Stats {
+        configs: entries.len(),
+        total_bytes: entries.iter().map(|entry| entry.size).sum(),
+        // Ties go to the first name, since entries are sorted.
+        largest: entries
+            .iter()
+            .rev()
+            .max_by_key(|entry| entry.size)
+            .map(sized),
+        smallest: entries.iter().min_by_key(|entry| entry.size).map(sized),
+        newest: entries
+            .iter()
+            .filter_map(|entry| Some((entry, entry.modified?)))
+            .max_by_key(|(_, modified)| *modified)
+            .map(|(entry, modified)| Modified {
+                name: entry.name.clone(),
+                modified: timestamp(modified),
+            }),
+        described: count(|entry| entry.meta.description.is_some()),
+        disabled: count(|entry| entry.meta.disabled),
+        fragments: count(|entry| entry.meta.fragment),
+        tagged: count(|entry| !entry.meta.tags.is_empty()),
+        active: entries
+            .iter()
+            .find(|entry| entry.active)
+            .map(|entry| entry.name.clone()),
+    })
+}
+
+/// Print a summary of the store, or with `json` the same figures as JSON.
+pub fn print_stats(json: bool) -> io::Result<()> {
+    let stats = collect()?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).map_err(io::Error::other)?
+        );
+        return Ok(());
+    }
+
+    println!("Configs:       {}", stats.configs);
+    println!("Total size:    {} bytes", stats.total_bytes);
+    if let Some(largest) = &stats.largest {
+        println!("Largest:       {} ({} bytes)", largest.name, largest.size);
+    }
+    if let Some(smallest) = &stats.smallest {
+        println!("Smallest:      {} ({} bytes)", smallest.name, smallest.size);
+    }
+    if let Some(newest) = &stats.newest {
+        println!("Last modified: {} ({})", newest.name, newest.modified);
+    }
+    println!("Described:     {}", stats.described);
+    println!("Disabled:      {}", stats.disabled);
+    println!("Fragments:     {}", stats.fragments);
+    println!("Tagged:        {}", stats.tagged);
+    println!(
+        "Active:        {}",
+        stats.active.as_deref().unwrap_or("none")
+    );
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use regex::{Captures, Regex};
+use std::{env, fs, io};
+
+use crate::{
+    existing_config_path,
+    format::{self, Format},
+    inherit, meta,
+};
+
+/// Parse a `--var key=value` pair.
+pub fn parse_var(var: &str) -> Result<(String, String), String> {
+    let (key, value) = var
+        .split_once('=')
+        .ok_or(format!("expected key=value, got {var}"))?;
+    Ok((key.trim().to_string(), value.to_string()))
+}
+
+/// Replace every `${key}` in `template` with its value from `vars`,
+/// returning the keys that have none, which are left as they are.
+fn replace(template: &str, vars: &[(String, String)]) -> (String, Vec<String>) {
+    let placeholder = Regex::new(r"\$\{([A-Za-z0-9_.-]+)\}").expect("placeholder pattern is valid");
+    let mut unset = vec![];
+
+    let rendered = placeholder.replace_all(template, |captures: &Captures| {
+        let key = &captures[1];
+        // Later --var flags win, like repeated environment assignments.
+        match vars.iter().rev().find(|(name, _)| name == key) {
+            Some((_, value)) => value.clone(),
+            None => {
+                unset.push(key.to_string());
+                captures[0].to_string()
+            }
+        }
+    });
+
+    unset.sort();
+    unset.dedup();
+    (rendered.into_owned(), unset)
+}
+
+fn placeholders(keys: &[String]) -> String {
+    keys.iter()
+        .map(|key| format!("${{{key}}}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Replace every `${key}` in `template` with its value from `vars`. Unknown
+/// keys are an error, or left as they are with `allow_unset`.
+pub fn substitute(
+    template: &str,
+    vars: &[(String, String)],
+    allow_unset: bool,
+) -> io::Result<String> {
+    let (rendered, unset) = replace(template, vars);
+    if !unset.is_empty() && !allow_unset {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "No value for {}, pass --var or --allow-unset",
+                placeholders(&unset)
+            ),
+        ));
+    }
+    Ok(rendered)
+}
+
+/// The values a template config is rendered with on switch: `hostname`,
+/// `arch` and `os` for this machine, and `env.NAME` for every environment
+/// variable.
+fn machine_vars() -> Vec<(String, String)> {
+    let mut vars = vec![
+        ("hostname".to_string(), meta::hostname()),
+        ("arch".to_string(), env::consts::ARCH.to_string()),
+        ("os".to_string(), env::consts::OS.to_string()),
+    ];
+    vars.extend(env::vars_os().filter_map(|(var, value)| {
+        Some((
+            format!("env.{}", var.into_string().ok()?),
+            value.into_string().ok()?,
+        ))
+    }));
+    vars
+}
+
+/// What the live config must contain for `name` when it cannot simply be
+/// linked: its `extends` chain merged into one file, and for a template
+/// config the placeholders filled in with [`machine_vars`]. `None` when the
+/// stored file can be linked as it is. A placeholder without a value is an
+/// error, so a half-rendered config never goes live.
+pub fn live_content(name: &str) -> io::Result<Option<String>> {
+    let resolved = inherit::resolve(name)?
+        .map(|table| toml::to_string(&table).map_err(io::Error::other))
+        .transpose()?;
+    if !meta::get(name)?.template {
+        return Ok(resolved);
+    }
+
+    let content = match resolved {
+        Some(content) => content,
+        None => fs::read_to_string(existing_config_path(name)?)?,
+    };
+    let (rendered, unset) = replace(&content, &machine_vars());
+    if !unset.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "No value for {} in {name}, set the environment variable or remove the placeholder",
+                placeholders(&unset)
+            ),
+        ));
+    }
+    format::parse(&rendered, Format::Toml, name)?;
+    Ok(Some(rendered))
+}
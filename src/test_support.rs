@@ -0,0 +1,34 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{GlobalArgs, GLOBAL};
+
+/// Held by every test that touches the store, since they share one cargo
+/// home and the active config in it.
+static STORE: Mutex<()> = Mutex::new(());
+
+/// Point the tool at a cargo home of its own in the temp dir, made once per
+/// test run, and hold the store until the returned guard is dropped.
+pub fn cargo_home() -> (MutexGuard<'static, ()>, PathBuf) {
+    let guard = STORE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let global = GLOBAL.get_or_init(|| {
+        let dir = env::temp_dir().join(format!("cargo-config-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("the test cargo home can be created");
+        GlobalArgs {
+            target_cargo_home: Some(dir),
+            ..GlobalArgs::default()
+        }
+    });
+    let dir = global
+        .target_cargo_home
+        .clone()
+        .expect("store tests run against the test cargo home");
+    (guard, dir)
+}
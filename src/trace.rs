@@ -0,0 +1,74 @@
+use std::{env, io};
+
+use crate::{current_config, get, lint, project_dir, resolve_live_config};
+
+/// `CARGO_*` variables that set a config key, by the top-level table their
+/// name starts with, sorted. Values are left out, they may be tokens.
+fn env_overrides() -> Vec<String> {
+    let prefixes = lint::TOP_LEVEL
+        .iter()
+        .map(|table| format!("CARGO_{}_", table.to_uppercase().replace('-', "_")))
+        .collect::<Vec<_>>();
+    let mut vars = env::vars_os()
+        .filter_map(|(var, _)| var.into_string().ok())
+        .filter(|var| prefixes.iter().any(|prefix| var.starts_with(prefix)))
+        .collect::<Vec<_>>();
+    vars.sort();
+    vars
+}
+
+/// Print every source cargo would read config from here, highest precedence
+/// first, with what each contributes. Nothing is changed.
+pub fn trace() -> io::Result<()> {
+    println!(
+        "Cargo reads config from, highest precedence first (from {}):",
+        project_dir()?.display()
+    );
+    let mut step = 0;
+    let mut next = || {
+        step += 1;
+        step
+    };
+
+    let vars = env_overrides();
+    if vars.is_empty() {
+        println!("{}. Environment: no CARGO_* config variables set", next());
+    } else {
+        println!("{}. Environment: {}", next(), vars.join(", "));
+        println!("   Each overrides the key it names in every file below");
+    }
+
+    let live = resolve_live_config()?;
+    for path in get::config_files()?.into_iter().rev() {
+        let source = if path == live {
+            match current_config()? {
+                Some(name) => format!("cargo home, the active config {name}"),
+                None => "cargo home, not a stored config".to_string(),
+            }
+        } else {
+            "project".to_string()
+        };
+        let contributes = match get::read(&path) {
+            Ok(table) if table.is_empty() => "sets nothing".to_string(),
+            Ok(table) => format!(
+                "sets {}",
+                table.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+            Err(err) => format!("cannot be read, cargo will fail: {err}"),
+        };
+        println!("{}. {} ({source})", next(), path.display());
+        println!("   {contributes}");
+    }
+    if !live.exists() {
+        println!(
+            "The cargo home config {} does not exist, so it is not read",
+            live.display()
+        );
+    }
+
+    println!();
+    println!("A key set by an earlier source wins, tables are merged key by key,");
+    println!("and arrays such as build.rustflags are joined from every source.");
+    println!("`--config` flags on the cargo command line override all of these.");
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use owo_colors::OwoColorize;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    check_duplicate, check_new_name, clipboard, config_path, create_private_file,
+    existing_config_path, find_duplicate,
+    format::{self, Format},
+    meta::{self, ConfigMeta, Provenance},
+    warning, ExportArgs,
+};
+
+/// Prefix of the leading comment lines that carry a config's metadata in an
+/// exported file. Being comments, they leave the file usable as-is by cargo.
+const SIDECAR_PREFIX: &str = "#@";
+
+/// Key names that suggest a value is a secret.
+const SECRET_HINTS: [&str; 3] = ["token", "secret", "password"];
+
+/// Write a config to `--output`, or stdout if not given.
+pub fn export_config(args: &ExportArgs) -> io::Result<()> {
+    let name = args.value.as_str();
+    let mut content = fs::read_to_string(existing_config_path(name)?)?;
+
+    let table = format::parse(&content, Format::Toml, name)?;
+
+    // Filtering means reserializing, which drops comments and formatting, so
+    // the file is passed through untouched unless asked to.
+    let table = if args.only.is_empty() && args.except.is_empty() {
+        table
+    } else {
+        let table = table
+            .into_iter()
+            .filter(|(section, _)| args.only.is_empty() || args.only.contains(section))
+            .filter(|(section, _)| !args.except.contains(section))
+            .collect::<toml::Table>();
+        content = toml::to_string(&table).map_err(io::Error::other)?;
+        table
+    };
+
+    if args.format != Format::Toml {
+        if (args.include_sidecars || args.annotate) && args.format == Format::Json {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "JSON has no comments to carry metadata, drop --include-sidecars and --annotate",
+            ));
+        }
+        content = format::serialize(&table, args.format)?;
+    }
+
+    let mut secrets = vec![];
+    find_secrets("", &table, &mut secrets);
+    for key in secrets {
+        // stderr, so the warning cannot end up in an exported file.
+        warning::eprint(format!(
+            "{key} looks like a secret, consider --except {}",
+            key.split('.').next().unwrap_or_default()
+        ));
+    }
+
+    let mut exported = String::new();
+    if args.include_sidecars || args.annotate {
+        let mut meta = match args.include_sidecars {
+            true => meta::get(name)?,
+            false => ConfigMeta::default(),
+        };
+        if args.annotate {
+            meta.provenance = Some(Provenance::here(name));
+        }
+        if !meta.is_empty() {
+            let header = toml::to_string(&meta).map_err(io::Error::other)?;
+            for line in header.lines() {
+                exported.push_str(&format!("{SIDECAR_PREFIX} {line}\n"));
+            }
+        }
+    }
+    exported.push_str(&content);
+
+    match &args.output {
+        Some(path) => fs::write(path, exported),
+        None if args.clipboard => clipboard::copy(&exported),
+        None => io::stdout().write_all(exported.as_bytes()),
+    }
+}
+
+/// Store the file at `path` as a new config, restoring any metadata exported
+/// alongside it. Files in another `format` (guessed from the extension if
+/// not given) are converted to TOML. A symlink is refused unless
+/// `follow_symlinks` is set, in which case its target is copied: the stored
+/// config is always a regular file, so it can be hard linked on switch.
+/// Returns the name it was stored under, or `None` if `skip_duplicates` is
+/// set and an existing config already has the same content.
+pub fn import_config(
+    path: &Path,
+    name: Option<&str>,
+    format: Option<Format>,
+    follow_symlinks: bool,
+    skip_duplicates: bool,
+) -> io::Result<Option<String>> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot name a config after {}, pass --name", path.display()),
+            ))?,
+    };
+
+    check_new_name(&name)?;
+    if !follow_symlinks && fs::symlink_metadata(path)?.is_symlink() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is a symlink, import its target or drop --no-follow-symlinks",
+                path.display()
+            ),
+        ));
+    }
+    let content = fs::read_to_string(path)?;
+    let (sidecar, content) = split_sidecars(&content);
+    let content = match format.unwrap_or_else(|| Format::from_path(path)) {
+        Format::Toml => content.to_string(),
+        format => {
+            let table = format::parse(content, format, &path.display().to_string())?;
+            format::serialize(&table, Format::Toml)?
+        }
+    };
+
+    if skip_duplicates {
+        if let Some(existing) = find_duplicate(&name, &content)? {
+            println!(
+                "Skipped:   {}  {} is identical to {existing}, not imported",
+                "↷".cyan(),
+                path.display()
+            );
+            return Ok(None);
+        }
+    }
+    check_duplicate(&name, &content, false)?;
+
+    let mut file = create_private_file(&config_path(&name)?).map_err(|err| match err.kind() {
+        io::ErrorKind::AlreadyExists => io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{name} already exists"),
+        ),
+        _ => err,
+    })?;
+    tracing::debug!(from = %path.display(), name, "importing config");
+    file.write_all(content.as_bytes())?;
+
+    if !sidecar.is_empty() {
+        let meta = toml::from_str(&sidecar).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Exported metadata in {} is not valid: {err}",
+                    path.display()
+                ),
+            )
+        })?;
+        meta::set(&name, meta)?;
+    }
+
+    Ok(Some(name))
+}
+
+/// Collect the dotted keys in `table` whose name hints at a secret value.
+fn find_secrets(prefix: &str, table: &toml::Table, found: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        if let toml::Value::Table(table) = value {
+            find_secrets(&path, table, found);
+        } else if SECRET_HINTS
+            .iter()
+            .any(|hint| key.to_lowercase().contains(hint))
+        {
+            found.push(path);
+        }
+    }
+}
+
+/// Split the metadata header off an exported config.
+fn split_sidecars(content: &str) -> (String, &str) {
+    let mut sidecar = String::new();
+    let mut rest = content;
+
+    while let Some(line) = rest.strip_prefix(SIDECAR_PREFIX) {
+        let (line, next) = line.split_once('\n').unwrap_or((line, ""));
+        sidecar.push_str(line.strip_prefix(' ').unwrap_or(line));
+        sidecar.push('\n');
+        rest = next;
+    }
+
+    (sidecar, rest)
+}
@@ -0,0 +1,132 @@
+use owo_colors::OwoColorize;
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config_path, existing_config_path, resolve_config_dir, store_files};
+
+/// A removed config waiting in `.trash/`, stored as
+/// `{name}.toml.{unix secs}.{nanos}` with any groups in the name as
+/// directories. Entries trashed before the nanoseconds were added have none.
+pub struct TrashEntry {
+    pub name: String,
+    pub removed_at: SystemTime,
+    pub path: PathBuf,
+}
+
+fn trash_dir() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push(".trash");
+    Ok(path)
+}
+
+/// Move a config into the trash instead of deleting it.
+pub fn trash_config(name: &str) -> io::Result<()> {
+    let source = existing_config_path(name)?;
+    let dir = trash_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    // Down to the nanosecond, so removing a name again right after does not
+    // replace the copy trashed before.
+    let removed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut target = dir;
+    target.push(format!(
+        "{name}.toml.{}.{:09}",
+        removed_at.as_secs(),
+        removed_at.subsec_nanos()
+    ));
+    if let Some(group) = target.parent() {
+        fs::create_dir_all(group)?;
+    }
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is already in the trash", target.display()),
+        ));
+    }
+
+    tracing::debug!(from = %source.display(), to = %target.display(), "trashing config");
+    fs::rename(source, target)
+}
+
+/// Every trashed config, oldest first.
+pub fn entries() -> io::Result<Vec<TrashEntry>> {
+    let dir = trash_dir()?;
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut entries = store_files(&dir)?
+        .into_iter()
+        .filter_map(|(file_name, entry)| {
+            let (name, removed_at) = file_name.rsplit_once(".toml.")?;
+            let (secs, nanos) = removed_at.split_once('.').unwrap_or((removed_at, "0"));
+            let removed_at = Duration::new(secs.parse().ok()?, nanos.parse().ok()?);
+            Some(TrashEntry {
+                name: name.to_string(),
+                removed_at: UNIX_EPOCH + removed_at,
+                path: entry.path(),
+            })
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.removed_at);
+    Ok(entries)
+}
+
+pub fn list_trash() -> io::Result<()> {
+    let entries = entries()?;
+    if entries.is_empty() {
+        println!("Trash is empty");
+        return Ok(());
+    }
+
+    println!("Trashed profiles:");
+    for entry in entries {
+        println!(
+            "- {} (removed {})",
+            entry.name,
+            humantime::format_rfc3339_seconds(entry.removed_at)
+        );
+    }
+    Ok(())
+}
+
+/// Put the most recently trashed copy of `name` back into the store.
+pub fn restore_config(name: &str) -> io::Result<()> {
+    let entry = entries()?
+        .into_iter()
+        .rfind(|entry| entry.name == name)
+        .ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{name} is not in the trash"),
+        ))?;
+
+    let target = config_path(name)?;
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{name} already exists, rename or remove it before restoring"),
+        ));
+    }
+
+    tracing::debug!(from = %entry.path.display(), to = %target.display(), "restoring config");
+    fs::rename(entry.path, target)
+}
+
+pub fn empty_trash() -> io::Result<()> {
+    let count = entries()?.len();
+    match fs::remove_dir_all(trash_dir()?) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+
+    println!(
+        "Success:   {}  Permanently removed {count} trashed profile(s)",
+        "✓".green()
+    );
+    Ok(())
+}
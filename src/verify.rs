@@ -0,0 +1,228 @@
+use owo_colors::OwoColorize;
+use serde_json::Value;
+use std::{collections::BTreeMap, env, fs, io, process::Command};
+
+use crate::{
+    config_names, current_config, global, parallel, resolve_config_dir, template, warning,
+};
+
+/// Check that a config parses, optionally comparing it against what cargo
+/// actually resolves.
+pub fn verify_config(name: Option<&str>, against_cargo: bool) -> io::Result<()> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => current_config()?.ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "No config is active, pass a config name to verify",
+        ))?,
+    };
+
+    let table = parse_config(&name)?;
+    println!("Success:   {}  {name} is valid TOML", "✓".green());
+
+    if against_cargo {
+        // Cargo only reads the active config, so any other would show every
+        // key as ignored.
+        if current_config()?.as_deref() != Some(name.as_str()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{name} is not active, switch to it before comparing against cargo"),
+            ));
+        }
+        // Compare what the switch wrote, with `extends` resolved and
+        // placeholders filled in, not the stored file.
+        let table = match template::live_content(&name)? {
+            Some(content) => content.parse::<toml::Table>().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("The composed {name} is not valid TOML: {err}"),
+                )
+            })?,
+            None => table,
+        };
+        compare_with_cargo(&name, &table)?;
+    }
+
+    Ok(())
+}
+
+/// Check that every config parses, `jobs` at a time. Results are printed in
+/// name order once all are done, and any invalid config fails the command.
+pub fn verify_all(jobs: usize) -> io::Result<()> {
+    let names = config_names()?;
+    let results = parallel::map(&names, jobs, |name| parse_config(name).map(drop));
+
+    let mut invalid = 0;
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok(()) => println!("Success:   {}  {name} is valid TOML", "✓".green()),
+            Err(err) => {
+                invalid += 1;
+                warning::print(err);
+            }
+        }
+    }
+
+    if invalid > 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{invalid} of {} configs are not valid TOML", names.len()),
+        ));
+    }
+    Ok(())
+}
+
+/// Read and parse a stored config.
+pub fn parse_config(name: &str) -> io::Result<toml::Table> {
+    let mut path = resolve_config_dir()?;
+    path.push(format!("{name}.toml"));
+
+    let content = fs::read_to_string(path)
+        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, format!("{name} does not exist")))?;
+
+    content.parse::<toml::Table>().map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{name} is not valid TOML: {err}"),
+        )
+    })
+}
+
+/// Run `cargo config get` and flag every managed key that cargo either does
+/// not report or reports with a different value.
+fn compare_with_cargo(name: &str, table: &toml::Table) -> io::Result<()> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(cargo);
+    command.args(["-Zunstable-options", "config", "get", "--format", "json"]);
+    if let Some(dir) = &global().target_cargo_home {
+        command.env("CARGO_HOME", dir);
+    }
+    let output = command.output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "`cargo config get` is not supported by this cargo: {}",
+                stderr.lines().next().unwrap_or_default()
+            ),
+        ));
+    }
+
+    let effective: Value = serde_json::from_slice(&output.stdout).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse `cargo config get` output: {err}"),
+        )
+    })?;
+
+    let managed = serde_json::to_value(table).map_err(io::Error::other)?;
+
+    let mut managed_keys = BTreeMap::new();
+    let mut effective_keys = BTreeMap::new();
+    flatten("", &managed, &mut managed_keys);
+    flatten("", &effective, &mut effective_keys);
+
+    let mut mismatches = 0;
+    for (key, value) in &managed_keys {
+        // `cargo config get` leaves environment overrides out of a full dump,
+        // so those have to be checked for separately.
+        if let Some((var, effective)) = env_override(key) {
+            mismatches += 1;
+            warning::print(format!(
+                "{key} is overridden by {var}{}",
+                shown_values(key, value, &effective)
+            ));
+            continue;
+        }
+
+        match effective_keys.get(key) {
+            None => {
+                mismatches += 1;
+                warning::print(format!("{key} is ignored by cargo"));
+            }
+            Some(effective) if !same_value(value, effective) => {
+                mismatches += 1;
+                warning::print(format!(
+                    "{key} is overridden{}",
+                    shown_values(key, value, effective)
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    if mismatches == 0 {
+        println!("Success:   {}  cargo uses every key in {name}", "✓".green());
+    }
+
+    Ok(())
+}
+
+/// The set and effective values for a mismatch on `key`, left out for keys
+/// such as `registry.token` whose values are credentials.
+fn shown_values(key: &str, set: &Value, effective: &dyn std::fmt::Display) -> String {
+    let key = key.to_lowercase();
+    if ["token", "credential", "secret", "password"]
+        .iter()
+        .any(|word| key.contains(word))
+    {
+        return String::new();
+    }
+    format!(" (set: {set}, effective: {effective})")
+}
+
+/// Flatten nested tables into dotted keys, leaving arrays and scalars as
+/// leaves.
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Cargo concatenates arrays from every config file, so a managed array is
+/// honoured as long as all of its elements made it into the effective one.
+fn same_value(managed: &Value, effective: &Value) -> bool {
+    match (managed, effective) {
+        (Value::Array(managed), Value::Array(effective)) => {
+            managed.iter().all(|item| effective.contains(item))
+        }
+        _ => managed == effective,
+    }
+}
+
+/// The `CARGO_*` environment variable overriding `key` and its value, if one
+/// is set.
+pub fn env_override(key: &str) -> Option<(String, String)> {
+    let var = format!("CARGO_{}", key.to_uppercase().replace(['.', '-'], "_"));
+    env::var(&var).ok().map(|value| (var, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credential_values_are_never_shown() {
+        let token = Value::from("cio_secret");
+        assert_eq!(shown_values("registry.token", &token, &"cio_other"), "");
+        assert_eq!(
+            shown_values("registries.work.Token", &token, &"cio_other"),
+            ""
+        );
+        assert!(shown_values("build.jobs", &Value::from(2), &4).contains("effective: 4"));
+    }
+}
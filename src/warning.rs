@@ -0,0 +1,34 @@
+use owo_colors::OwoColorize;
+use std::{fmt::Display, sync::Mutex};
+
+/// Every warning printed so far, for `--exit-on-warning`.
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+fn record(message: &str) {
+    tracing::warn!("{message}");
+    if let Ok(mut warnings) = WARNINGS.lock() {
+        warnings.push(message.to_string());
+    }
+}
+
+/// Print a warning to stdout.
+pub fn print(message: impl Display) {
+    let message = message.to_string();
+    println!("Warning:   {}  {message}", "⚠".yellow());
+    record(&message);
+}
+
+/// Print a warning to stderr, for commands whose stdout carries data.
+pub fn eprint(message: impl Display) {
+    let message = message.to_string();
+    eprintln!("Warning:   {}  {message}", "⚠".yellow());
+    record(&message);
+}
+
+/// The warnings printed so far, oldest first.
+pub fn emitted() -> Vec<String> {
+    WARNINGS
+        .lock()
+        .map(|warnings| warnings.clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,63 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::{checksum, global, resolve_config_dir, resolve_live_config, settings, warning};
+
+/// Whether to check the live config for changes made outside the tool, set by
+/// `--watch-cargo-home` or the `watch-cargo-home` setting.
+pub fn enabled() -> bool {
+    global().watch_cargo_home || settings::load().is_ok_and(|s| s.watch_cargo_home)
+}
+
+fn hash_path() -> io::Result<PathBuf> {
+    let mut path = resolve_config_dir()?;
+    path.push("cargo-config-live-hash");
+    Ok(path)
+}
+
+pub fn has_record() -> bool {
+    hash_path().is_ok_and(|path| path.is_file())
+}
+
+/// The live config's contents, or `None` if there is no live config.
+fn live_content() -> io::Result<Option<Vec<u8>>> {
+    match fs::read(resolve_live_config()?) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Warn if the live config no longer matches the hash recorded at the end of
+/// the last run, meaning something else (rustup, an IDE, a script) rewrote it.
+/// The warning goes to stderr so it never mixes with exported output.
+pub fn check_live_config() -> io::Result<()> {
+    let recorded = match fs::read_to_string(hash_path()?) {
+        Ok(recorded) => recorded,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    // An empty record means there was no live config.
+    let unchanged = match (recorded.trim(), live_content()?) {
+        ("", content) => content.is_none(),
+        (_, None) => false,
+        (recorded, Some(content)) => checksum::matches_tagged(recorded, &content),
+    };
+    if !unchanged {
+        let live = resolve_live_config()?;
+        warning::eprint(format!(
+            "{} changed outside cargo-config-profiles since the last run",
+            live.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Record the live config's hash for the next run to compare against.
+pub fn record_live_config() -> io::Result<()> {
+    let hash = live_content()?
+        .map(|content| checksum::tagged(&content))
+        .unwrap_or_default();
+    tracing::debug!(hash, "recording live config hash");
+    fs::write(hash_path()?, hash)
+}
@@ -0,0 +1,201 @@
+use dialoguer::{theme::ColorfulTheme, Confirm, Input};
+use std::io::{self, IsTerminal, Write};
+use toml::{Table, Value};
+
+use crate::can_prompt;
+
+/// Whether to prompt with `dialoguer`, which draws on the terminal. Answers
+/// piped in under `--force-interactive` are read line by line instead, since
+/// `dialoguer` only reads from a terminal.
+fn on_terminal() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Read the trimmed answer to `question` from piped input, `None` for a
+/// blank one. The end of input aborts, so a closed pipe does not loop
+/// forever.
+fn read_answer(question: &str) -> io::Result<Option<String>> {
+    print!("{question} ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "Input ended, no config was created",
+        ));
+    }
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+/// Ask `question` and return the trimmed answer, `None` for a blank one.
+fn ask(question: &str) -> io::Result<Option<String>> {
+    ask_valid(question, |answer| Ok(answer.to_string()))
+}
+
+/// Ask until the answer is blank or `parse` accepts it.
+fn ask_valid<T>(
+    question: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> io::Result<Option<T>> {
+    if on_terminal() {
+        let answer: String = Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(question)
+            .allow_empty(true)
+            .validate_with(|answer: &String| match answer.trim() {
+                "" => Ok(()),
+                answer => parse(answer).map(drop),
+            })
+            .interact_text()?;
+        let answer = answer.trim();
+        return match answer.is_empty() {
+            true => Ok(None),
+            false => parse(answer).map(Some).map_err(io::Error::other),
+        };
+    }
+
+    loop {
+        let Some(answer) = read_answer(question)? else {
+            return Ok(None);
+        };
+        match parse(&answer) {
+            Ok(value) => return Ok(Some(value)),
+            Err(err) => println!("  {err}, try again or leave it blank"),
+        }
+    }
+}
+
+fn ask_yes(question: &str) -> io::Result<bool> {
+    if on_terminal() {
+        return Ok(Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(question)
+            .default(false)
+            .interact()?);
+    }
+    Ok(read_answer(&format!("{question} [y/N]"))?
+        .is_some_and(|answer| matches!(answer.as_str(), "y" | "Y" | "yes" | "Yes")))
+}
+
+/// A registry name as cargo accepts it in `[registries.<name>]`.
+fn parse_registry_name(name: &str) -> Result<String, String> {
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("{name:?} is not a valid registry name"));
+    }
+    Ok(name.to_string())
+}
+
+/// A registry index URL, plain or `sparse+`, with a host or a `file` path.
+fn parse_index(url: &str) -> Result<String, String> {
+    let plain = url.strip_prefix("sparse+").unwrap_or(url);
+    let valid = match plain.split_once("://") {
+        Some(("file", path)) => !path.is_empty(),
+        Some(("https" | "http" | "ssh" | "git", rest)) => {
+            rest.split('/').next().is_some_and(|host| !host.is_empty())
+        }
+        _ => false,
+    };
+    if !valid || url.contains(char::is_whitespace) {
+        return Err(format!(
+            "{url:?} is not an index URL, such as https://example.com/index"
+        ));
+    }
+    Ok(url.to_string())
+}
+
+fn parse_jobs(jobs: &str) -> Result<i64, String> {
+    match jobs.parse::<i64>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(format!("{jobs:?} is not a positive number")),
+    }
+}
+
+/// A target triple such as `x86_64-unknown-linux-gnu`. Only the shape is
+/// checked, cargo knows far more targets than could be listed here.
+fn parse_target(target: &str) -> Result<String, String> {
+    let parts = target.split('-').collect::<Vec<_>>();
+    let valid = parts.len() >= 2
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        });
+    if !valid {
+        return Err(format!(
+            "{target:?} is not a target triple, such as x86_64-unknown-linux-gnu"
+        ));
+    }
+    Ok(target.to_string())
+}
+
+/// Insert `value` under the nested `keys`, creating the tables on the way.
+fn insert(table: &mut Table, keys: &[&str], value: Value) {
+    let (last, tables) = keys.split_last().expect("keys are never empty");
+    let mut table = table;
+    for key in tables {
+        table = table
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .expect("the wizard only creates tables here");
+    }
+    table.insert(last.to_string(), value);
+}
+
+/// Walk through the common config settings on the terminal and return the
+/// config the answers make, as TOML. Every question can be skipped with a
+/// blank answer.
+pub fn build() -> io::Result<String> {
+    if !can_prompt() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--interactive needs a terminal to ask on",
+        ));
+    }
+
+    println!("Leave an answer blank to skip it.");
+    let mut config = Table::new();
+
+    if let Some(name) = ask_valid("Alternate registry name?", parse_registry_name)? {
+        let index = loop {
+            if let Some(index) = ask_valid(&format!("Index URL for {name}?"), parse_index)? {
+                break index;
+            }
+            println!("  A registry needs an index URL");
+        };
+        insert(
+            &mut config,
+            &["registries", &name, "index"],
+            Value::String(index),
+        );
+        if ask_yes(&format!(
+            "Make {name} the default for cargo publish and friends?"
+        ))? {
+            insert(&mut config, &["registry", "default"], Value::String(name));
+        }
+    }
+
+    if let Some(jobs) = ask_valid("Parallel build jobs?", parse_jobs)? {
+        insert(&mut config, &["build", "jobs"], Value::Integer(jobs));
+    }
+
+    if ask_yes("Work offline, without touching the network?")? {
+        insert(&mut config, &["net", "offline"], Value::Boolean(true));
+    }
+
+    if let Some(target) = ask_valid("Target to set a linker for?", parse_target)? {
+        if let Some(linker) = ask(&format!("Linker for {target}?"))? {
+            insert(
+                &mut config,
+                &["target", &target, "linker"],
+                Value::String(linker),
+            );
+        }
+    }
+
+    toml::to_string(&config).map_err(io::Error::other)
+}